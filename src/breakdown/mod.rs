@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use crate::duration::get_workout_step_duration;
+use crate::error::EstimateError;
+use crate::mileage::{get_workout_step_mileage, pace_alert};
+use crate::models::*;
+
+pub fn get_breakdown(pace_map: &PaceMap, workout: &Workout) -> Result<Breakdown, EstimateError> {
+    let mut breakdown = Breakdown {
+        steps: Vec::new(),
+        phases: HashMap::new(),
+        zones: HashMap::new(),
+    };
+
+    for step in workout.expand()? {
+        push_step(&mut breakdown, pace_map, step)?;
+    }
+
+    Ok(breakdown)
+}
+
+fn push_step(
+    breakdown: &mut Breakdown,
+    pace_map: &PaceMap,
+    expanded: ExpandedStep,
+) -> Result<(), EstimateError> {
+    let step = &expanded.step;
+    let estimate = Estimate {
+        mileage: get_workout_step_mileage(pace_map, step)?,
+        duration: get_workout_step_duration(pace_map, step)?,
+    };
+
+    // Steps with a pace, bpm, power or zone range target but no single zone are not attributed
+    let zone = step
+        .alerts
+        .iter()
+        .find_map(|alert| match alert {
+            Alert::HeartRate(zone) => Some(zone.clone()),
+            _ => None,
+        })
+        .or_else(|| match pace_alert(step) {
+            None => Some(pace_map.default.clone()),
+            Some(_) => None,
+        });
+
+    add_estimate(
+        breakdown
+            .phases
+            .entry(expanded.phase.clone())
+            .or_insert_with(Estimate::zero),
+        &estimate,
+    );
+    if let Some(zone) = &zone {
+        add_estimate(
+            breakdown
+                .zones
+                .entry(zone.clone())
+                .or_insert_with(Estimate::zero),
+            &estimate,
+        );
+    }
+
+    breakdown.steps.push(StepEstimate {
+        step: expanded.step.clone(),
+        phase: expanded.phase,
+        block: expanded.block,
+        rep: expanded.rep,
+        zone,
+        estimate,
+    });
+    Ok(())
+}
+
+fn add_estimate(total: &mut Estimate, estimate: &Estimate) {
+    total.mileage.min += estimate.mileage.min;
+    total.mileage.max += estimate.mileage.max;
+    total.duration.min += estimate.duration.min;
+    total.duration.max += estimate.duration.max;
+    total.mileage.open |= estimate.mileage.open;
+    total.duration.open |= estimate.duration.open;
+}
+
+impl Estimate {
+    fn zero() -> Self {
+        Estimate {
+            mileage: Mileage {
+                min: 0.0,
+                max: 0.0,
+                open: false,
+            },
+            duration: WorkoutDuration {
+                min: 0.0,
+                max: 0.0,
+                open: false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,109 @@
+use super::*;
+use crate::fixtures::PACE_MAP;
+use crate::mileage::length_unit_to_miles;
+use crate::parser::parse_workout;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_breakdown_steps() {
+    let workout = parse_workout(
+        "1 km warmup + 2 x (1 min @z3 + 2 min recovery) + 1 km @4:30/km + 1 km cooldown",
+    )
+    .unwrap();
+    let breakdown = get_breakdown(&PACE_MAP, &workout).unwrap();
+
+    let tags: Vec<_> = breakdown
+        .steps
+        .iter()
+        .map(|s| (s.phase.clone(), s.block, s.rep))
+        .collect();
+    assert_eq!(
+        tags,
+        vec![
+            (Phase::Warmup, None, None),
+            (Phase::Interval, Some(0), Some(1)),
+            (Phase::Recovery, Some(0), Some(1)),
+            (Phase::Interval, Some(0), Some(2)),
+            (Phase::Recovery, Some(0), Some(2)),
+            (Phase::Interval, Some(1), None),
+            (Phase::Cooldown, None, None),
+        ]
+    );
+
+    // Z3 pace: 5-4 min/km for 1 minute
+    let km = length_unit_to_miles(LengthUnit::Kilometers);
+    let work = &breakdown.steps[1];
+    assert_eq!(work.zone, Some(HeartRateZone::Z3));
+    assert!((work.estimate.mileage.min - km / 5.0).abs() < 1e-9);
+    assert!((work.estimate.mileage.max - km / 4.0).abs() < 1e-9);
+    assert_eq!(work.estimate.duration.min, 60.0);
+
+    // Pace targeted steps are not attributed to a zone
+    assert_eq!(breakdown.steps[5].zone, None);
+    assert!((breakdown.steps[5].estimate.duration.max - 270.0).abs() < 1e-6);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_breakdown_subtotals() {
+    let workout =
+        parse_workout("1 km warmup + 2 x (1 min @z3 + 2 min recovery) + 1 km cooldown").unwrap();
+    let breakdown = get_breakdown(&PACE_MAP, &workout).unwrap();
+
+    // Default Z2 pace: 6:15-5:00 min/km for 4 minutes
+    let km = length_unit_to_miles(LengthUnit::Kilometers);
+    let recovery = &breakdown.phases[&Phase::Recovery];
+    assert_eq!(recovery.duration.min, 240.0);
+    assert!((recovery.mileage.min - 0.64 * km).abs() < 1e-9);
+    assert!((recovery.mileage.max - 0.8 * km).abs() < 1e-9);
+
+    let warmup = &breakdown.phases[&Phase::Warmup];
+    assert_eq!(warmup.mileage.min, km);
+    assert!((warmup.duration.min - 300.0).abs() < 1e-6);
+    assert!((warmup.duration.max - 375.0).abs() < 1e-6);
+
+    // Warmup, recoveries and cooldown all run in the default zone
+    let z2 = &breakdown.zones[&HeartRateZone::Z2];
+    assert!((z2.duration.min - (300.0 + 240.0 + 300.0)).abs() < 1e-6);
+    let z3 = &breakdown.zones[&HeartRateZone::Z3];
+    assert_eq!(z3.duration.min, 120.0);
+    assert_eq!(breakdown.zones.len(), 2);
+
+    // Subtotals add up to the aggregate estimates
+    let mileage = crate::mileage::get_mileage(&PACE_MAP, &workout).unwrap();
+    let total: f64 = breakdown.phases.values().map(|e| e.mileage.min).sum();
+    assert!((total - mileage.min).abs() < 1e-9);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_breakdown_nested_repeats() {
+    let workout = parse_workout("2 x (3 x (200m @z3 + 200m recovery) + 3 min recovery)").unwrap();
+    let breakdown = get_breakdown(&PACE_MAP, &workout).unwrap();
+    assert_eq!(breakdown.steps.len(), 2 * (3 * 2 + 1));
+
+    let reps: Vec<_> = breakdown.steps[..7].iter().map(|s| s.rep).collect();
+    assert_eq!(
+        reps,
+        vec![
+            Some(1),
+            Some(1),
+            Some(2),
+            Some(2),
+            Some(3),
+            Some(3),
+            Some(1)
+        ]
+    );
+    assert_eq!(breakdown.steps[13].rep, Some(2));
+    assert!(breakdown.steps.iter().all(|s| s.block == Some(0)));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_breakdown_zone_with_multiple_alerts() {
+    let workout = parse_workout("1 km @4:30/km @z3 + 1 km @4:30/km + 1 km @180spm").unwrap();
+    let breakdown = get_breakdown(&PACE_MAP, &workout).unwrap();
+    let zones: Vec<_> = breakdown.steps.iter().map(|s| s.zone.clone()).collect();
+    assert_eq!(
+        zones,
+        vec![Some(HeartRateZone::Z3), None, Some(HeartRateZone::Z2)]
+    );
+}
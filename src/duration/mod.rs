@@ -0,0 +1,126 @@
+use crate::error::EstimateError;
+use crate::mileage::{get_pace_range, length_unit_to_miles, time_to_seconds};
+use crate::models::*;
+
+pub fn get_duration(
+    pace_map: &PaceMap,
+    workout: &Workout,
+) -> Result<WorkoutDuration, EstimateError> {
+    let mut min = 0.0;
+    let mut max = 0.0;
+    let mut open = false;
+
+    if let Some(warmup) = &workout.warmup {
+        let warmup_duration = get_workout_step_duration(pace_map, warmup)?;
+        min += warmup_duration.min;
+        max += warmup_duration.max;
+        open |= warmup_duration.open;
+    }
+
+    for interval in &workout.intervals {
+        let interval_duration = get_interval_block_duration(pace_map, interval)?;
+        min += interval_duration.min;
+        max += interval_duration.max;
+        open |= interval_duration.open;
+    }
+
+    if let Some(cooldown) = &workout.cooldown {
+        let cooldown_duration = get_workout_step_duration(pace_map, cooldown)?;
+        min += cooldown_duration.min;
+        max += cooldown_duration.max;
+        open |= cooldown_duration.open;
+    }
+
+    Ok(WorkoutDuration { min, max, open })
+}
+
+fn get_interval_block_duration(
+    pace_map: &PaceMap,
+    interval: &IntervalBlock,
+) -> Result<WorkoutDuration, EstimateError> {
+    let mut min = 0.0;
+    let mut max = 0.0;
+    let mut open = false;
+
+    for rep in &interval.steps {
+        let rep_duration = match rep {
+            IntervalRep::Step(step) => get_workout_step_duration(pace_map, &step.step)?,
+            IntervalRep::Block(block) => get_interval_block_duration(pace_map, block)?,
+        };
+        min += rep_duration.min;
+        max += rep_duration.max;
+        open |= rep_duration.open;
+    }
+
+    let repeats = interval.repeats.unwrap_or(1) as f64;
+    Ok(WorkoutDuration {
+        min: min * repeats,
+        max: max * repeats,
+        open,
+    })
+}
+
+fn seconds_per_mile(pace: &Pace) -> f64 {
+    pace.time / length_unit_to_miles(pace.unit)
+}
+
+fn distance_unit_to_seconds(
+    pace_range: &PaceRange,
+    value: f64,
+    unit: LengthUnit,
+) -> WorkoutDuration {
+    let miles = value * length_unit_to_miles(unit);
+    let min_pace_seconds = miles * seconds_per_mile(&pace_range.min);
+    let max_pace_seconds = miles * seconds_per_mile(&pace_range.max);
+    // Ranges may be written fast-to-slow or slow-to-fast
+    WorkoutDuration {
+        min: min_pace_seconds.min(max_pace_seconds),
+        max: min_pace_seconds.max(max_pace_seconds),
+        open: false,
+    }
+}
+
+pub(crate) fn get_workout_step_duration(
+    pace_map: &PaceMap,
+    step: &WorkoutStep,
+) -> Result<WorkoutDuration, EstimateError> {
+    match step.goal {
+        Goal::Distance { value, unit } => {
+            let pace_range = get_pace_range(pace_map, step)?;
+            Ok(distance_unit_to_seconds(&pace_range, value, unit))
+        }
+        Goal::Race { distance } => {
+            let pace_range = get_pace_range(pace_map, step)?;
+            let (value, unit) = distance.length();
+            Ok(distance_unit_to_seconds(&pace_range, value, unit))
+        }
+        Goal::Duration { value, unit } => Ok(WorkoutDuration {
+            min: time_to_seconds(value, unit),
+            max: time_to_seconds(value, unit),
+            open: false,
+        }),
+        Goal::FirstOf {
+            distance,
+            length_unit,
+            duration,
+            time_unit,
+        } => {
+            let pace_range = get_pace_range(pace_map, step)?;
+            let paced = distance_unit_to_seconds(&pace_range, distance, length_unit);
+            let seconds = time_to_seconds(duration, time_unit);
+            Ok(WorkoutDuration {
+                min: paced.min.min(seconds),
+                max: paced.max.min(seconds),
+                open: false,
+            })
+        }
+        Goal::Open => Ok(WorkoutDuration {
+            min: 0.0,
+            max: 0.0,
+            open: true,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests;
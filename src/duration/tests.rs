@@ -0,0 +1,87 @@
+use super::*;
+use crate::fixtures::PACE_MAP;
+use crate::parser::parse_workout;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_empty_workout() {
+    let workout = Workout {
+        warmup: None,
+        intervals: vec![],
+        cooldown: None,
+    };
+    let duration = get_duration(&PACE_MAP, &workout).unwrap();
+    assert_eq!(duration.min, 0.0);
+    assert_eq!(duration.max, 0.0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_duration_goals() {
+    let workout =
+        parse_workout("10 min warmup + 3 x (90 sec + 1 min recovery) + 1 hr cooldown").unwrap();
+    let duration = get_duration(&PACE_MAP, &workout).unwrap();
+    // 600 + 3 * (90 + 60) + 3600 seconds
+    assert_eq!(duration.min, 4650.0);
+    assert_eq!(duration.max, 4650.0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_distance_goals() {
+    let workout =
+        parse_workout("1 km warmup + 2 x (1 km @z3 + 0.5 km @4:00/km) + 1 km cooldown").unwrap();
+    let duration = get_duration(&PACE_MAP, &workout).unwrap();
+    // Default Z2 pace: 6:15-5:00 min/km for the warmup and cooldown = 10-12.5 minutes
+    // Z3 pace: 5-4 min/km, repeated 2 times = 8-10 minutes
+    // Threshold pace: 2 minutes, repeated 2 times = 4 minutes
+    assert!((duration.min - 22.0 * 60.0).abs() < 1e-6);
+    assert!((duration.max - 26.5 * 60.0).abs() < 1e-6);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_nested_repeats() {
+    let workout = parse_workout("2 x (3 x (1 min + 30 sec recovery) + 2 min recovery)").unwrap();
+    let duration = get_duration(&PACE_MAP, &workout).unwrap();
+    // 2 * (3 * 90 + 120) seconds
+    assert_eq!(duration.min, 780.0);
+    assert_eq!(duration.max, 780.0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_first_of_goals() {
+    let workout = parse_workout("1 km or 6 min").unwrap();
+    let duration = get_duration(&PACE_MAP, &workout).unwrap();
+    // Default Z2 pace: the kilometer takes 5-6.25 minutes, capped at 6
+    assert!((duration.min - 300.0).abs() < 1e-6);
+    assert_eq!(duration.max, 360.0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_open_goals() {
+    let workout = parse_workout("open warmup + 3 x (90 sec + lap recovery)").unwrap();
+    let duration = get_duration(&PACE_MAP, &workout).unwrap();
+    // Open steps count as zero but are flagged
+    assert_eq!(duration.min, 270.0);
+    assert_eq!(duration.max, 270.0);
+    assert!(duration.open);
+
+    let workout = parse_workout("3 x (90 sec + 1 min recovery)").unwrap();
+    assert!(!get_duration(&PACE_MAP, &workout).unwrap().open);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_distance_unit_to_seconds() {
+    // Range written fast-to-slow still yields min <= max
+    let pace_range = PaceRange {
+        min: Pace {
+            time: 4.0 * 60.0,
+            unit: LengthUnit::Kilometers,
+        },
+        max: Pace {
+            time: 5.0 * 60.0,
+            unit: LengthUnit::Kilometers,
+        },
+    };
+    let duration = distance_unit_to_seconds(&pace_range, 10.0, LengthUnit::Kilometers);
+    assert!((duration.min - 2400.0).abs() < 1e-6);
+    assert!((duration.max - 3000.0).abs() < 1e-6);
+}
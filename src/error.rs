@@ -0,0 +1,297 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "wasm")]
+use tsify::Tsify;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::models::{HeartRateZone, TrainingPace};
+use crate::parser::Rule;
+
+/// Byte offsets into the source text, end excluded
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct Span {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Stable identifier of an error, safe to match on from the apps
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCode {
+    UnexpectedToken,
+    UnexpectedEnd,
+    NumberOutOfRange,
+    InvalidPaceSeconds,
+    ZeroRepeats,
+    ZeroGoal,
+    RecoveryFasterThanWork,
+    LongStep,
+    InvalidHeartRate,
+    InvalidPower,
+    InvalidCadence,
+    DuplicateAlert,
+    InvalidSpeed,
+    ZeroPaceSplit,
+    InvalidDuration,
+    UnsupportedDuration,
+    UnsupportedTarget,
+    InvalidPace,
+    EmptyWorkout,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found by `validate`, the span is only known when the workout was parsed from text
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: ErrorCode,
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, thiserror::Error)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[error("{message} at line {line}, column {column}")]
+pub struct DashError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub span: Span,
+    pub line: u32,
+    pub column: u32,
+    pub expected: Vec<String>,
+}
+
+/// Failure to unroll the repeats of a workout
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, thiserror::Error)]
+#[cfg_attr(feature = "ios", derive(uniffi::Error))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ExpandError {
+    #[error("Workout unrolls into more than {limit} steps")]
+    TooManySteps { limit: u64 },
+}
+
+/// Failure to estimate a workout against a pace map
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, thiserror::Error)]
+#[cfg_attr(feature = "ios", derive(uniffi::Error))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[allow(clippy::enum_variant_names)]
+pub enum EstimateError {
+    #[error("Pace map has no range for {zone}, needed by `{step}`")]
+    MissingZone { zone: HeartRateZone, step: String },
+    #[error("Pace map has no range for {pace}, needed by `{step}`")]
+    MissingTrainingPace { pace: TrainingPace, step: String },
+    #[error("Pace map has no heart rates, needed by `{step}`")]
+    MissingHeartRates { step: String },
+    #[error("Pace map has no power profile, needed by `{step}`")]
+    MissingPowerProfile { step: String },
+    #[error("{error}")]
+    Expand {
+        #[from]
+        error: ExpandError,
+    },
+}
+
+/// Failure to derive a pace map from a race result
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, thiserror::Error)]
+#[cfg_attr(feature = "ios", derive(uniffi::Error))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PaceMapError {
+    #[error("Cannot derive paces from `{goal}`, it is not a race distance")]
+    NotADistance { goal: String },
+    #[error("Cannot derive paces from running `{race}` in {time} seconds")]
+    InvalidResult { race: String, time: f64 },
+}
+
+/// Failure to write or read a FIT workout file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, thiserror::Error)]
+#[cfg_attr(feature = "ios", derive(uniffi::Error))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FitError {
+    #[error("Not a FIT file")]
+    InvalidHeader,
+    #[error("FIT file is corrupted, its checksum does not match")]
+    InvalidChecksum,
+    #[error("FIT file ends in the middle of a record")]
+    UnexpectedEnd,
+    #[error("FIT record of local type {local} comes before its definition")]
+    UndefinedMessage { local: u8 },
+    #[error("FIT file holds no workout")]
+    NotAWorkout,
+    #[error("Repeat at step {step} goes back to step {target}, which does not start a block")]
+    InvalidRepeat { step: u32, target: u32 },
+    #[error("{message}")]
+    InvalidWorkout { code: ErrorCode, message: String },
+    #[error("Workout has more steps than a FIT file holds")]
+    TooManySteps,
+    #[error("`{step}` does not fit in a FIT file")]
+    OutOfRange { step: String },
+    #[error("{error}")]
+    Estimate {
+        #[from]
+        error: EstimateError,
+    },
+}
+
+impl DashError {
+    pub(crate) fn at(pair: &pest::iterators::Pair<Rule>, code: ErrorCode, message: String) -> Self {
+        let (line, column) = pair.line_col();
+        DashError {
+            code,
+            message,
+            span: Span {
+                start: pair.as_span().start() as u32,
+                end: pair.as_span().end() as u32,
+            },
+            line: line as u32,
+            column: column as u32,
+            expected: Vec::new(),
+        }
+    }
+
+    pub(crate) fn from_diagnostic(diagnostic: Diagnostic, input: &str) -> Self {
+        let span = diagnostic.span.unwrap_or(Span { start: 0, end: 0 });
+        let before = &input[..span.start as usize];
+        let line = before.matches('\n').count() + 1;
+        let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+        DashError {
+            code: diagnostic.code,
+            message: diagnostic.message,
+            span,
+            line: line as u32,
+            column: column as u32,
+            expected: Vec::new(),
+        }
+    }
+
+    pub(crate) fn from_pest(error: pest::error::Error<Rule>, input: &str) -> Self {
+        let start = match error.location {
+            pest::error::InputLocation::Pos(pos) => pos,
+            pest::error::InputLocation::Span((start, _)) => start,
+        };
+        let (line, column) = match error.line_col {
+            pest::error::LineColLocation::Pos(pos) => pos,
+            pest::error::LineColLocation::Span(start, _) => start,
+        };
+
+        // Highlight the whole offending token rather than a single position
+        let end = start
+            + input[start..]
+                .find(|c: char| !(c.is_alphanumeric() || c == ':' || c == '.'))
+                .unwrap_or(input.len() - start);
+
+        let mut expected: Vec<String> = Vec::new();
+        let message = match error.variant {
+            pest::error::ErrorVariant::ParsingError { positives, .. } => {
+                for rule in positives {
+                    let word = describe_rule(rule).to_string();
+                    if !expected.contains(&word) {
+                        expected.push(word);
+                    }
+                }
+                match expected.len() {
+                    0 => "unexpected input".to_string(),
+                    1 => format!("expected {}", expected[0]),
+                    n => format!(
+                        "expected {} or {}",
+                        expected[..n - 1].join(", "),
+                        expected[n - 1]
+                    ),
+                }
+            }
+            pest::error::ErrorVariant::CustomError { message } => message,
+        };
+
+        DashError {
+            code: if start >= input.len() {
+                ErrorCode::UnexpectedEnd
+            } else {
+                ErrorCode::UnexpectedToken
+            },
+            message,
+            span: Span {
+                start: start as u32,
+                end: end as u32,
+            },
+            line: line as u32,
+            column: column as u32,
+            expected,
+        }
+    }
+}
+
+fn describe_rule(rule: Rule) -> &'static str {
+    match rule {
+        Rule::number => "a number",
+        Rule::miles | Rule::yards | Rule::feet | Rule::meter | Rule::kilometer => "a distance unit",
+        Rule::seconds | Rule::minutes | Rule::hours => "a time unit",
+        Rule::heart_rate_alert
+        | Rule::heart_rate_zone_range_alert
+        | Rule::zone_number
+        | Rule::z1
+        | Rule::z2
+        | Rule::z3
+        | Rule::z4
+        | Rule::z5 => "a heart rate zone",
+        Rule::pace_threshold_alert
+        | Rule::pace_range_alert
+        | Rule::pace
+        | Rule::pace_time
+        | Rule::pace_seconds
+        | Rule::pace_split => "a pace",
+        Rule::heart_rate_range_alert | Rule::heart_rate_threshold_alert | Rule::bpm => {
+            "a heart rate"
+        }
+        Rule::power_range_alert
+        | Rule::power_threshold_alert
+        | Rule::critical_power_alert
+        | Rule::watts => "a power",
+        Rule::cadence_range_alert | Rule::cadence_threshold_alert | Rule::spm => "a cadence",
+        Rule::speed_range_alert
+        | Rule::speed_threshold_alert
+        | Rule::kilometers_per_hour
+        | Rule::miles_per_hour => "a speed",
+        Rule::clock_duration
+        | Rule::compact_duration
+        | Rule::compact_hours
+        | Rule::compact_minutes
+        | Rule::compact_seconds => "a duration",
+        Rule::race_goal
+        | Rule::half_marathon
+        | Rule::marathon
+        | Rule::ten_k
+        | Rule::five_k
+        | Rule::race_mile => "a race distance",
+        Rule::open_goal => "`open`",
+        Rule::training_pace_alert
+        | Rule::easy_pace
+        | Rule::marathon_pace
+        | Rule::half_marathon_pace
+        | Rule::threshold_pace
+        | Rule::interval_pace
+        | Rule::repetition_pace => "a training pace",
+        Rule::recovery => "`recovery`",
+        Rule::EOI => "end of input",
+        _ => "a workout step",
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+
+use crate::mileage::{get_pace_range, length_unit_to_miles};
+use crate::models::*;
+
+// A wider tolerance would let the low bound reach zero and accept any reading
+const MAX_TOLERANCE: f64 = 0.5;
+
+/// Tells whether live readings are within an alert, without flickering at its bounds
+pub struct AlertEvaluator {
+    target: Option<Target>,
+    config: EvaluatorConfig,
+    readings: VecDeque<f64>,
+    status: Option<AlertStatus>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Metric {
+    HeartRate,
+    Pace,
+    Power,
+    Cadence,
+}
+
+/// Bounds of an alert, paces are turned into speeds so that faster is always higher
+struct Target {
+    metric: Metric,
+    min: f64,
+    max: f64,
+}
+
+impl AlertEvaluator {
+    /// Settings out of range are clamped, a negative hysteresis would make leaving easier
+    /// than getting back in
+    pub fn new(alert: &Alert, pace_map: &PaceMap, config: EvaluatorConfig) -> Self {
+        AlertEvaluator {
+            target: get_target(alert, pace_map),
+            config: EvaluatorConfig {
+                tolerance: config.tolerance.clamp(0.0, MAX_TOLERANCE),
+                smoothing: config.smoothing.max(1),
+                hysteresis: config.hysteresis.max(0.0),
+            },
+            readings: VecDeque::new(),
+            status: None,
+        }
+    }
+
+    /// Status after the last reading, none until the sample carries the metric of the alert
+    /// or when the pace map cannot resolve the target
+    pub fn status(&self) -> Option<AlertStatus> {
+        self.status
+    }
+
+    pub fn push(&mut self, sample: &Sample) -> Option<AlertStatus> {
+        let target = self.target.as_ref()?;
+        let Some(reading) = get_reading(target.metric, sample) else {
+            return self.status;
+        };
+
+        self.readings.push_back(reading);
+        while self.readings.len() > self.config.smoothing as usize {
+            self.readings.pop_front();
+        }
+        let value = self.readings.iter().sum::<f64>() / self.readings.len() as f64;
+
+        let min = target.min * (1.0 - self.config.tolerance);
+        let max = target.max * (1.0 + self.config.tolerance);
+        // Getting back in range takes a margin past the bound, never past the middle
+        let middle = (min + max) / 2.0;
+        let (enter_min, enter_max) = match self.status {
+            Some(AlertStatus::TooSlow) => ((min * (1.0 + self.config.hysteresis)).min(middle), max),
+            Some(AlertStatus::TooFast) => (min, (max * (1.0 - self.config.hysteresis)).max(middle)),
+            _ => (min, max),
+        };
+
+        self.status = Some(if value < enter_min {
+            AlertStatus::TooSlow
+        } else if value > enter_max {
+            AlertStatus::TooFast
+        } else {
+            AlertStatus::InRange
+        });
+        self.status
+    }
+}
+
+fn get_target(alert: &Alert, pace_map: &PaceMap) -> Option<Target> {
+    let target = |metric, min: u32, max: u32| Target {
+        metric,
+        min: min as f64,
+        max: max as f64,
+    };
+    match alert {
+        Alert::HeartRateThreshold { bpm, ceiling } => Some(Target {
+            metric: Metric::HeartRate,
+            min: if *ceiling { 0.0 } else { *bpm as f64 },
+            max: *bpm as f64,
+        }),
+        Alert::HeartRateRange { min, max } => Some(target(Metric::HeartRate, *min, *max)),
+        Alert::PowerThreshold { watts } => Some(target(Metric::Power, *watts, *watts)),
+        Alert::PowerRange { min, max } => Some(target(Metric::Power, *min, *max)),
+        Alert::CriticalPower { percent } => {
+            let watts = pace_map.power.as_ref()?.critical_power as f64 * percent / 100.0;
+            Some(Target {
+                metric: Metric::Power,
+                min: watts,
+                max: watts,
+            })
+        }
+        Alert::CadenceThreshold { spm } => Some(target(Metric::Cadence, *spm, *spm)),
+        Alert::CadenceRange { min, max } => Some(target(Metric::Cadence, *min, *max)),
+        // Zones and named paces are run by pace
+        _ => {
+            let step = WorkoutStep {
+                goal: Goal::Open,
+                alerts: vec![alert.clone()],
+            };
+            let range = get_pace_range(pace_map, &step).ok()?;
+            let (a, b) = (speed(&range.min), speed(&range.max));
+            Some(Target {
+                metric: Metric::Pace,
+                min: a.min(b),
+                max: a.max(b),
+            })
+        }
+    }
+}
+
+fn get_reading(metric: Metric, sample: &Sample) -> Option<f64> {
+    match metric {
+        Metric::HeartRate => sample.heart_rate.map(|bpm| bpm as f64),
+        Metric::Pace => sample.pace.as_ref().map(speed),
+        Metric::Power => sample.power.map(|watts| watts as f64),
+        Metric::Cadence => sample.cadence.map(|spm| spm as f64),
+    }
+}
+
+/// Miles per second
+fn speed(pace: &Pace) -> f64 {
+    length_unit_to_miles(pace.unit) / pace.time
+}
+
+#[cfg(test)]
+mod tests;
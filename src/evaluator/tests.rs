@@ -0,0 +1,127 @@
+use super::*;
+use crate::fixtures::{sample, PACE_MAP};
+use wasm_bindgen_test::*;
+
+fn config(tolerance: f64, smoothing: u32, hysteresis: f64) -> EvaluatorConfig {
+    EvaluatorConfig {
+        tolerance,
+        smoothing,
+        hysteresis,
+    }
+}
+
+fn heart_rate(bpm: u32) -> Sample {
+    Sample {
+        heart_rate: Some(bpm),
+        ..sample(0.0, 0.0)
+    }
+}
+
+fn pace(minutes_per_km: f64) -> Sample {
+    Sample {
+        pace: Some(Pace {
+            time: minutes_per_km * 60.0,
+            unit: LengthUnit::Kilometers,
+        }),
+        ..sample(0.0, 0.0)
+    }
+}
+
+fn range(min: u32, max: u32) -> Alert {
+    Alert::HeartRateRange { min, max }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_evaluator_compares_readings() {
+    let mut evaluator = AlertEvaluator::new(&range(140, 150), &PACE_MAP, config(0.0, 1, 0.0));
+    assert_eq!(evaluator.status(), None);
+    assert_eq!(evaluator.push(&heart_rate(130)), Some(AlertStatus::TooSlow));
+    assert_eq!(evaluator.push(&heart_rate(145)), Some(AlertStatus::InRange));
+    assert_eq!(evaluator.push(&heart_rate(160)), Some(AlertStatus::TooFast));
+    assert_eq!(evaluator.status(), Some(AlertStatus::TooFast));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_evaluator_tolerance() {
+    let mut evaluator = AlertEvaluator::new(&range(140, 150), &PACE_MAP, config(0.02, 1, 0.0));
+    // 2% of 140 and 150 is 2.8 and 3 bpm
+    assert_eq!(evaluator.push(&heart_rate(138)), Some(AlertStatus::InRange));
+    assert_eq!(evaluator.push(&heart_rate(153)), Some(AlertStatus::InRange));
+    assert_eq!(evaluator.push(&heart_rate(136)), Some(AlertStatus::TooSlow));
+    assert_eq!(evaluator.push(&heart_rate(155)), Some(AlertStatus::TooFast));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_evaluator_smoothing() {
+    let mut evaluator = AlertEvaluator::new(&range(140, 150), &PACE_MAP, config(0.0, 3, 0.0));
+    assert_eq!(evaluator.push(&heart_rate(145)), Some(AlertStatus::InRange));
+    assert_eq!(evaluator.push(&heart_rate(145)), Some(AlertStatus::InRange));
+    // A single spike is averaged out
+    assert_eq!(evaluator.push(&heart_rate(160)), Some(AlertStatus::InRange));
+    assert_eq!(evaluator.push(&heart_rate(160)), Some(AlertStatus::TooFast));
+    // Only the last three readings count
+    assert_eq!(evaluator.push(&heart_rate(120)), Some(AlertStatus::InRange));
+    assert_eq!(evaluator.push(&heart_rate(120)), Some(AlertStatus::TooSlow));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_evaluator_hysteresis() {
+    let mut evaluator = AlertEvaluator::new(&range(140, 150), &PACE_MAP, config(0.0, 1, 0.02));
+    assert_eq!(evaluator.push(&heart_rate(139)), Some(AlertStatus::TooSlow));
+    // Back at the bound is not enough, 2% past it is
+    assert_eq!(evaluator.push(&heart_rate(141)), Some(AlertStatus::TooSlow));
+    assert_eq!(evaluator.push(&heart_rate(143)), Some(AlertStatus::InRange));
+    // Leaving takes no margin
+    assert_eq!(evaluator.push(&heart_rate(151)), Some(AlertStatus::TooFast));
+    assert_eq!(evaluator.push(&heart_rate(149)), Some(AlertStatus::TooFast));
+    assert_eq!(evaluator.push(&heart_rate(146)), Some(AlertStatus::InRange));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_evaluator_hysteresis_stops_at_the_middle() {
+    let alert = Alert::PowerThreshold { watts: 200 };
+    let mut evaluator = AlertEvaluator::new(&alert, &PACE_MAP, config(0.0, 1, 0.1));
+    let power = |watts: u32| Sample {
+        power: Some(watts),
+        ..sample(0.0, 0.0)
+    };
+    assert_eq!(evaluator.push(&power(190)), Some(AlertStatus::TooSlow));
+    assert_eq!(evaluator.push(&power(200)), Some(AlertStatus::InRange));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_evaluator_clamps_config() {
+    // A 100% tolerance would take anything down to 0bpm as in range
+    let mut evaluator = AlertEvaluator::new(&range(140, 150), &PACE_MAP, config(1.0, 0, 0.0));
+    assert_eq!(evaluator.push(&heart_rate(60)), Some(AlertStatus::TooSlow));
+    assert_eq!(evaluator.push(&heart_rate(80)), Some(AlertStatus::InRange));
+
+    // A negative hysteresis would count readings outside the target as back in range
+    let mut evaluator = AlertEvaluator::new(&range(140, 150), &PACE_MAP, config(0.0, 1, -0.1));
+    assert_eq!(evaluator.push(&heart_rate(130)), Some(AlertStatus::TooSlow));
+    assert_eq!(evaluator.push(&heart_rate(135)), Some(AlertStatus::TooSlow));
+    assert_eq!(evaluator.push(&heart_rate(140)), Some(AlertStatus::InRange));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_evaluator_resolves_zones() {
+    let alert = Alert::HeartRate(HeartRateZone::Z2);
+    let mut evaluator = AlertEvaluator::new(&alert, &PACE_MAP, config(0.0, 1, 0.0));
+    assert_eq!(evaluator.push(&pace(6.5)), Some(AlertStatus::TooSlow));
+    assert_eq!(evaluator.push(&pace(5.5)), Some(AlertStatus::InRange));
+    assert_eq!(evaluator.push(&pace(4.5)), Some(AlertStatus::TooFast));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_evaluator_without_reading() {
+    let mut evaluator = AlertEvaluator::new(&range(140, 150), &PACE_MAP, config(0.0, 1, 0.0));
+    assert_eq!(evaluator.push(&pace(5.5)), None);
+    assert_eq!(evaluator.push(&heart_rate(145)), Some(AlertStatus::InRange));
+    // The last status holds until the metric comes back
+    assert_eq!(evaluator.push(&pace(5.5)), Some(AlertStatus::InRange));
+
+    // Unresolvable targets are never evaluated
+    let alert = Alert::HeartRate(HeartRateZone::Z4);
+    let mut evaluator = AlertEvaluator::new(&alert, &PACE_MAP, config(0.0, 1, 0.0));
+    assert_eq!(evaluator.push(&pace(5.5)), None);
+}
@@ -0,0 +1,109 @@
+use crate::error::ExpandError;
+use crate::models::*;
+
+// Blocks are unrolled step by step before running or exporting them
+pub(crate) const MAX_UNROLLED_STEPS: u64 = 10_000;
+
+impl Workout {
+    /// Steps in the order they are run, with every repeat unrolled
+    pub fn expand(&self) -> Result<Vec<ExpandedStep>, ExpandError> {
+        if self.unrolled_steps() > MAX_UNROLLED_STEPS {
+            return Err(ExpandError::TooManySteps {
+                limit: MAX_UNROLLED_STEPS,
+            });
+        }
+
+        let mut steps = Vec::new();
+
+        if let Some(warmup) = &self.warmup {
+            push_step(&mut steps, warmup, Phase::Warmup, None, &[]);
+        }
+
+        for (index, interval) in self.intervals.iter().enumerate() {
+            push_interval_block(&mut steps, interval, index as u32, &mut Vec::new());
+        }
+
+        if let Some(cooldown) = &self.cooldown {
+            push_step(&mut steps, cooldown, Phase::Cooldown, None, &[]);
+        }
+
+        Ok(steps)
+    }
+
+    /// Number of steps `expand` gives, saturating rather than overflowing
+    pub(crate) fn unrolled_steps(&self) -> u64 {
+        let ends = self.warmup.is_some() as u64 + self.cooldown.is_some() as u64;
+        self.intervals.iter().fold(ends, |total, block| {
+            total.saturating_add(block.unrolled_steps())
+        })
+    }
+}
+
+impl IntervalBlock {
+    fn unrolled_steps(&self) -> u64 {
+        let steps = self.steps.iter().fold(0u64, |total, rep| {
+            total.saturating_add(match rep {
+                IntervalRep::Step(_) => 1,
+                IntervalRep::Block(inner) => inner.unrolled_steps(),
+            })
+        });
+        steps.saturating_mul(self.repeats.unwrap_or(1) as u64)
+    }
+}
+
+fn push_interval_block(
+    steps: &mut Vec<ExpandedStep>,
+    interval: &IntervalBlock,
+    block: u32,
+    sets: &mut Vec<Repetition>,
+) {
+    let repeats = interval.repeats.unwrap_or(1);
+    for i in 0..repeats {
+        if interval.repeats.is_some() {
+            sets.push(Repetition {
+                rep: i + 1,
+                repeats,
+            });
+        }
+        for step in &interval.steps {
+            match step {
+                IntervalRep::Step(step) => {
+                    let phase = if step.has_recovery {
+                        Phase::Recovery
+                    } else {
+                        Phase::Interval
+                    };
+                    push_step(steps, &step.step, phase, Some(block), sets);
+                }
+                IntervalRep::Block(inner) => push_interval_block(steps, inner, block, sets),
+            }
+        }
+        if interval.repeats.is_some() {
+            sets.pop();
+        }
+    }
+}
+
+fn push_step(
+    steps: &mut Vec<ExpandedStep>,
+    step: &WorkoutStep,
+    phase: Phase,
+    block: Option<u32>,
+    sets: &[Repetition],
+) {
+    // Steps report the repetition of the innermost repeat they belong to
+    let rep = sets.last();
+    steps.push(ExpandedStep {
+        index: steps.len() as u32,
+        step: step.clone(),
+        recovery: phase == Phase::Recovery,
+        phase,
+        block,
+        rep: rep.map(|rep| rep.rep),
+        repeats: rep.map(|rep| rep.repeats),
+        sets: sets.to_vec(),
+    });
+}
+
+#[cfg(test)]
+mod tests;
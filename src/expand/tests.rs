@@ -0,0 +1,95 @@
+use super::*;
+use crate::parser::parse_workout;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_expand_empty_workout() {
+    let workout = Workout {
+        warmup: None,
+        intervals: vec![],
+        cooldown: None,
+    };
+    assert!(workout.expand().unwrap().is_empty());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_expand() {
+    let workout =
+        parse_workout("1 mile warmup + 2 x (400m @z4 + 200m recovery) + 1 km + 1 mile cooldown")
+            .unwrap();
+    let steps = workout.expand().unwrap();
+
+    let summary: Vec<_> = steps
+        .iter()
+        .map(|s| {
+            (
+                s.index,
+                s.phase.clone(),
+                s.block,
+                s.rep,
+                s.repeats,
+                s.recovery,
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (0, Phase::Warmup, None, None, None, false),
+            (1, Phase::Interval, Some(0), Some(1), Some(2), false),
+            (2, Phase::Recovery, Some(0), Some(1), Some(2), true),
+            (3, Phase::Interval, Some(0), Some(2), Some(2), false),
+            (4, Phase::Recovery, Some(0), Some(2), Some(2), true),
+            (5, Phase::Interval, Some(1), None, None, false),
+            (6, Phase::Cooldown, None, None, None, false),
+        ]
+    );
+    assert_eq!(steps[3].step.to_string(), "400 m @z4");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_expand_nested_repeats() {
+    let workout = parse_workout("2 x (3 x (200m + 200m recovery) + 3 min recovery)").unwrap();
+    let steps = workout.expand().unwrap();
+    assert_eq!(steps.len(), 14);
+
+    // Steps of the inner block count its repeats, the block recovery counts the outer ones
+    let reps: Vec<(Option<u32>, Option<u32>)> =
+        steps[..7].iter().map(|s| (s.rep, s.repeats)).collect();
+    assert_eq!(
+        reps,
+        vec![
+            (Some(1), Some(3)),
+            (Some(1), Some(3)),
+            (Some(2), Some(3)),
+            (Some(2), Some(3)),
+            (Some(3), Some(3)),
+            (Some(3), Some(3)),
+            (Some(1), Some(2)),
+        ]
+    );
+    assert_eq!(steps[13].rep, Some(2));
+    assert!(steps.iter().all(|s| s.block == Some(0)));
+
+    // Every repeat around a step is kept, outermost first
+    let set = |rep, repeats| Repetition { rep, repeats };
+    assert_eq!(steps[0].sets, vec![set(1, 2), set(1, 3)]);
+    assert_eq!(steps[6].sets, vec![set(1, 2)]);
+    assert_eq!(steps[7].sets, vec![set(2, 2), set(1, 3)]);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_expand_too_many_steps() {
+    // Structured workouts skip the validation of the parser
+    let mut workout = parse_workout("1 mile warmup + 2 x (1 km + 1 min recovery)").unwrap();
+    workout.intervals[0].repeats = Some(4_000_000_000);
+    assert_eq!(
+        workout.expand(),
+        Err(ExpandError::TooManySteps {
+            limit: MAX_UNROLLED_STEPS
+        })
+    );
+
+    workout.intervals[0].repeats = Some(4_999);
+    assert_eq!(workout.expand().unwrap().len(), 9_999);
+}
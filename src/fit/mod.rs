@@ -0,0 +1,798 @@
+use std::collections::HashMap;
+
+use crate::error::{Diagnostic, ErrorCode, EstimateError, FitError, Severity};
+use crate::mileage::{get_pace_range, length_unit_to_miles, pace_alert, time_to_seconds};
+use crate::models::*;
+use crate::parser::exact_duration;
+use crate::validate::validate;
+
+// Global message numbers of the FIT profile
+const FILE_ID: u16 = 0;
+const WORKOUT: u16 = 26;
+const WORKOUT_STEP: u16 = 27;
+
+// Base types, the high bit flags multi-byte types
+const ENUM: u8 = 0x00;
+const STRING: u8 = 0x07;
+const UINT16: u8 = 0x84;
+const UINT32: u8 = 0x86;
+
+const FILE_TYPE_WORKOUT: u8 = 5;
+const MANUFACTURER_DEVELOPMENT: u16 = 255;
+const SPORT_RUNNING: u8 = 1;
+
+const DURATION_TIME: u8 = 0; // Milliseconds
+const DURATION_DISTANCE: u8 = 1; // Centimeters
+const DURATION_OPEN: u8 = 5; // Until the lap button is pressed
+const DURATION_REPEAT: u8 = 6; // Back to the step in the value, the count is in the target value
+const DURATION_REPEAT_UNTIL: std::ops::RangeInclusive<u8> = 7..=13; // Until a time, distance, ...
+
+const TARGET_SPEED: u8 = 0; // Millimeters per second
+const TARGET_HEART_RATE: u8 = 1; // Zone, or custom bpm offset by 100
+const TARGET_OPEN: u8 = 2;
+const TARGET_CADENCE: u8 = 3;
+const TARGET_POWER: u8 = 4; // Custom watts offset by 1000
+
+const INTENSITY_ACTIVE: u8 = 0;
+const INTENSITY_REST: u8 = 1;
+const INTENSITY_WARMUP: u8 = 2;
+const INTENSITY_COOLDOWN: u8 = 3;
+const INTENSITY_RECOVERY: u8 = 4;
+
+const INVALID_ENUM: u8 = 0xFF;
+const INVALID_UINT32: u32 = 0xFFFF_FFFF;
+
+// Message indexes are 16 bits, the largest one meaning none
+const MAX_STEPS: usize = 0xFFFE;
+
+const PROTOCOL_VERSION: u8 = 0x20;
+const PROFILE_VERSION: u16 = 2100;
+
+/// A binary FIT workout file of the workout, for Garmin watches and Garmin Connect.
+///
+/// A FIT step has a single target, picked like the pace of an estimate: pace or speed, then
+/// power, then heart rate, then cadence. Zones become heart rate zones of the watch, other
+/// paces become speed ranges through the pace map. A step with a distance and a duration
+/// limit ends on the distance, as FIT steps cannot end on whichever comes first.
+pub fn to_fit(pace_map: &PaceMap, workout: &Workout, name: &str) -> Result<Vec<u8>, FitError> {
+    let mut steps = Vec::new();
+    if let Some(warmup) = &workout.warmup {
+        steps.push(get_fit_step(pace_map, warmup, INTENSITY_WARMUP)?);
+    }
+    for interval in &workout.intervals {
+        push_interval_block(&mut steps, pace_map, interval)?;
+    }
+    if let Some(cooldown) = &workout.cooldown {
+        steps.push(get_fit_step(pace_map, cooldown, INTENSITY_COOLDOWN)?);
+    }
+    if steps.len() > MAX_STEPS {
+        return Err(FitError::TooManySteps);
+    }
+
+    let mut writer = FitWriter::default();
+    writer.write_message(
+        0,
+        FILE_ID,
+        &[
+            Field::enumeration(0, FILE_TYPE_WORKOUT),
+            Field::uint16(1, MANUFACTURER_DEVELOPMENT),
+            Field::uint16(2, 0),
+        ],
+    );
+    writer.write_message(
+        1,
+        WORKOUT,
+        &[
+            Field::enumeration(4, SPORT_RUNNING),
+            Field::uint16(6, steps.len() as u16),
+            Field::string(8, name),
+        ],
+    );
+    for (index, step) in steps.iter().enumerate() {
+        writer.write_message(
+            2,
+            WORKOUT_STEP,
+            &[
+                Field::uint16(254, index as u16),
+                Field::enumeration(1, step.duration_type),
+                Field::uint32(2, step.duration_value),
+                Field::enumeration(3, step.target_type),
+                Field::uint32(4, step.target_value),
+                Field::uint32(5, step.low),
+                Field::uint32(6, step.high),
+                Field::enumeration(7, step.intensity),
+            ],
+        );
+    }
+    Ok(writer.finish())
+}
+
+/// A workout read back from a FIT workout file.
+///
+/// Repeat steps become `n x (...)` blocks again. Durations and targets dash has no equivalent
+/// for, like calories or a share of the maximum heart rate, are dropped with a warning. The
+/// result is validated like a parsed workout, its errors fail the import.
+pub fn from_fit(bytes: &[u8]) -> Result<FitWorkout, FitError> {
+    let messages = read_messages(bytes)?;
+    let file_type = messages
+        .iter()
+        .find(|message| message.global == FILE_ID)
+        .and_then(|file_id| file_id.value(0));
+    if file_type.is_some_and(|file_type| file_type != FILE_TYPE_WORKOUT as u32) {
+        return Err(FitError::NotAWorkout);
+    }
+    let name = messages
+        .iter()
+        .find(|message| message.global == WORKOUT)
+        .and_then(|workout| workout.string(8));
+
+    // Repeats go back to steps by message index
+    let mut steps: Vec<&Message> = messages
+        .iter()
+        .filter(|message| message.global == WORKOUT_STEP)
+        .collect();
+    if steps.is_empty() {
+        return Err(FitError::NotAWorkout);
+    }
+    steps.sort_by_key(|step| step.value(254));
+
+    let mut warnings = Vec::new();
+    let mut reps: Vec<ImportedRep> = Vec::new();
+    for (index, step) in steps.iter().enumerate() {
+        let index = index as u32;
+        match step.value(1).map(|duration_type| duration_type as u8) {
+            Some(DURATION_REPEAT) => {
+                let target = step.value(2).unwrap_or(index);
+                let start = reps.iter().position(|rep| rep.start == target).ok_or(
+                    FitError::InvalidRepeat {
+                        step: index + 1,
+                        target: target + 1,
+                    },
+                )?;
+                let block = IntervalBlock {
+                    repeats: Some(step.value(4).unwrap_or(1)),
+                    steps: reps
+                        .split_off(start)
+                        .into_iter()
+                        .map(|rep| rep.rep)
+                        .collect(),
+                };
+                reps.push(ImportedRep {
+                    start: target,
+                    rep: IntervalRep::Block(block),
+                    intensity: None,
+                });
+            }
+            Some(duration_type) if DURATION_REPEAT_UNTIL.contains(&duration_type) => {
+                warnings.push(warning(
+                    ErrorCode::UnsupportedDuration,
+                    format!(
+                        "Step {} repeats on an unsupported condition, its steps run once",
+                        index + 1
+                    ),
+                ));
+            }
+            _ => {
+                let intensity = step.value(7).map(|intensity| intensity as u8);
+                let step = WorkoutStep {
+                    goal: get_goal(step, index, &mut warnings),
+                    alerts: get_alert(step, index, &mut warnings).into_iter().collect(),
+                };
+                reps.push(ImportedRep {
+                    start: index,
+                    rep: IntervalRep::Step(IntervalStep {
+                        step,
+                        has_recovery: matches!(
+                            intensity,
+                            Some(INTENSITY_REST | INTENSITY_RECOVERY)
+                        ),
+                    }),
+                    intensity,
+                });
+            }
+        }
+    }
+
+    // Warmup and cooldown are only split off while an interval block remains
+    let warmup = take_step(&mut reps, 0, INTENSITY_WARMUP);
+    let last = reps.len().saturating_sub(1);
+    let cooldown = take_step(&mut reps, last, INTENSITY_COOLDOWN);
+
+    // Top level steps are blocks of their own, like in the text form
+    let intervals = reps
+        .into_iter()
+        .map(|rep| match rep.rep {
+            IntervalRep::Step(step) => IntervalBlock {
+                repeats: None,
+                steps: vec![IntervalRep::Step(step)],
+            },
+            IntervalRep::Block(block) => block,
+        })
+        .collect();
+    let workout = Workout {
+        warmup,
+        intervals,
+        cooldown,
+    };
+
+    // Checked like a parsed workout, errors would fail later when running it
+    for diagnostic in validate(&workout) {
+        if diagnostic.severity == Severity::Error {
+            return Err(FitError::InvalidWorkout {
+                code: diagnostic.code,
+                message: diagnostic.message,
+            });
+        }
+        warnings.push(diagnostic);
+    }
+
+    Ok(FitWorkout {
+        name,
+        workout,
+        warnings,
+    })
+}
+
+/// A step or block rebuilt from FIT steps, with the index of the step it starts at
+struct ImportedRep {
+    start: u32,
+    rep: IntervalRep,
+    intensity: Option<u8>,
+}
+
+/// Removes the step at `position` when it runs at `intensity`
+fn take_step(reps: &mut Vec<ImportedRep>, position: usize, intensity: u8) -> Option<WorkoutStep> {
+    if reps.len() < 2 || reps[position].intensity != Some(intensity) {
+        return None;
+    }
+    match reps.remove(position).rep {
+        IntervalRep::Step(step) => Some(step.step),
+        IntervalRep::Block(_) => unreachable!(), // Blocks have no intensity
+    }
+}
+
+fn warning(code: ErrorCode, message: String) -> Diagnostic {
+    Diagnostic {
+        severity: Severity::Warning,
+        code,
+        message,
+        span: None,
+    }
+}
+
+fn get_goal(step: &Message, index: u32, warnings: &mut Vec<Diagnostic>) -> Goal {
+    let duration_type = step.value(1).map(|duration_type| duration_type as u8);
+    match (duration_type, step.value(2)) {
+        (Some(DURATION_TIME), Some(milliseconds)) if milliseconds.is_multiple_of(1000) => {
+            let (value, unit) = exact_duration(milliseconds as u64 / 1000);
+            Goal::Duration { value, unit }
+        }
+        (Some(DURATION_TIME), Some(milliseconds)) => Goal::Duration {
+            value: milliseconds as f64 / 1000.0,
+            unit: TimeUnit::Seconds,
+        },
+        (Some(DURATION_DISTANCE), Some(centimeters)) => get_distance_goal(centimeters),
+        (None | Some(DURATION_OPEN), _) => Goal::Open,
+        _ => {
+            warnings.push(warning(
+                ErrorCode::UnsupportedDuration,
+                format!(
+                    "Step {} ends on an unsupported condition, it is left open",
+                    index + 1
+                ),
+            ));
+            Goal::Open
+        }
+    }
+}
+
+/// Whole kilometers, then quarter miles within a meter, then meters
+fn get_distance_goal(centimeters: u32) -> Goal {
+    let meters = centimeters as f64 / 100.0;
+    let mile = length_unit_to_miles(LengthUnit::Miles) / length_unit_to_miles(LengthUnit::Meters);
+    let quarters = (meters / mile * 4.0).round();
+    let (value, unit) = if centimeters.is_multiple_of(100_000) {
+        (meters / 1000.0, LengthUnit::Kilometers)
+    } else if quarters > 0.0 && (meters - quarters * mile / 4.0).abs() < 1.0 {
+        (quarters / 4.0, LengthUnit::Miles)
+    } else {
+        (meters, LengthUnit::Meters)
+    };
+    Goal::Distance { value, unit }
+}
+
+fn get_alert(step: &Message, index: u32, warnings: &mut Vec<Diagnostic>) -> Option<Alert> {
+    let target_type = step.value(3)? as u8;
+    let zone = step.value(4).unwrap_or(0);
+    let (low, high) = (step.value(5), step.value(6));
+
+    let alert = match (target_type, zone, low, high) {
+        (TARGET_OPEN, ..) => return None,
+        (TARGET_HEART_RATE, 1..=5, ..) => Some(Alert::HeartRate(match zone {
+            1 => HeartRateZone::Z1,
+            2 => HeartRateZone::Z2,
+            3 => HeartRateZone::Z3,
+            4 => HeartRateZone::Z4,
+            _ => HeartRateZone::Z5,
+        })),
+        // Custom values up to 100 are shares of the maximum heart rate, a ceiling starts at 0%
+        (TARGET_HEART_RATE, 0, Some(0), Some(high)) if high > 100 => {
+            Some(Alert::HeartRateThreshold {
+                bpm: high - 100,
+                ceiling: true,
+            })
+        }
+        (TARGET_HEART_RATE, 0, Some(low), Some(high)) if low > 100 && high > 100 => {
+            let (min, max) = (low - 100, high - 100);
+            Some(if min == max {
+                Alert::HeartRateThreshold {
+                    bpm: min,
+                    ceiling: false,
+                }
+            } else {
+                Alert::HeartRateRange { min, max }
+            })
+        }
+        (TARGET_SPEED, 0, Some(low), Some(high)) if low > 0 && high > 0 => {
+            let pace = |speed: u32| Pace {
+                time: (1_000_000.0 / speed as f64).round(),
+                unit: LengthUnit::Kilometers,
+            };
+            let (min, max) = (pace(low.min(high)), pace(low.max(high)));
+            Some(if min == max {
+                Alert::PaceThreshold(min)
+            } else {
+                Alert::PaceRange { min, max }
+            })
+        }
+        (TARGET_CADENCE, 0, Some(low), Some(high)) if low > 0 && high > 0 => Some(if low == high {
+            Alert::CadenceThreshold { spm: low }
+        } else {
+            Alert::CadenceRange {
+                min: low.min(high),
+                max: low.max(high),
+            }
+        }),
+        // Custom values up to 1000 are shares of the functional threshold power
+        (TARGET_POWER, 0, Some(low), Some(high)) if low > 1000 && high > 1000 => {
+            let (min, max) = (low - 1000, high - 1000);
+            Some(if min == max {
+                Alert::PowerThreshold { watts: min }
+            } else {
+                Alert::PowerRange { min, max }
+            })
+        }
+        _ => None,
+    };
+
+    if alert.is_none() {
+        warnings.push(warning(
+            ErrorCode::UnsupportedTarget,
+            format!(
+                "Step {} has an unsupported target, it is dropped",
+                index + 1
+            ),
+        ));
+    }
+    alert
+}
+
+/// A workout_step message
+struct FitStep {
+    duration_type: u8,
+    duration_value: u32,
+    target_type: u8,
+    target_value: u32,
+    low: u32,
+    high: u32,
+    intensity: u8,
+}
+
+fn push_interval_block(
+    steps: &mut Vec<FitStep>,
+    pace_map: &PaceMap,
+    interval: &IntervalBlock,
+) -> Result<(), FitError> {
+    let start = steps.len() as u32;
+    for rep in &interval.steps {
+        match rep {
+            IntervalRep::Step(step) => {
+                let intensity = match step.has_recovery {
+                    true => INTENSITY_RECOVERY,
+                    false => INTENSITY_ACTIVE,
+                };
+                steps.push(get_fit_step(pace_map, &step.step, intensity)?);
+            }
+            IntervalRep::Block(block) => push_interval_block(steps, pace_map, block)?,
+        }
+    }
+
+    // Repeats are a step of their own closing the block
+    if let Some(repeats) = interval.repeats {
+        // The largest count would read back as none
+        if repeats == INVALID_UINT32 {
+            return Err(FitError::OutOfRange {
+                step: interval.to_string(),
+            });
+        }
+        steps.push(FitStep {
+            duration_type: DURATION_REPEAT,
+            duration_value: start,
+            target_type: INVALID_ENUM,
+            target_value: repeats,
+            low: INVALID_UINT32,
+            high: INVALID_UINT32,
+            intensity: INVALID_ENUM,
+        });
+    }
+    Ok(())
+}
+
+fn get_fit_step(
+    pace_map: &PaceMap,
+    step: &WorkoutStep,
+    intensity: u8,
+) -> Result<FitStep, FitError> {
+    let out_of_range = || FitError::OutOfRange {
+        step: step.to_string(),
+    };
+    // The largest value of a field reads back as none
+    let fit_value = |value: f64| {
+        let value = value.round();
+        match value >= 0.0 && value < INVALID_UINT32 as f64 {
+            true => Ok(value as u32),
+            false => Err(out_of_range()),
+        }
+    };
+    let offset = |value: u32, offset: u32| {
+        value
+            .checked_add(offset)
+            .filter(|value| *value != INVALID_UINT32)
+            .ok_or_else(out_of_range)
+    };
+
+    let meters = |value: f64, unit: LengthUnit| {
+        value * length_unit_to_miles(unit) / length_unit_to_miles(LengthUnit::Meters)
+    };
+    let centimeters = |value: f64, unit: LengthUnit| fit_value(meters(value, unit) * 100.0);
+    let (duration_type, duration_value) = match step.goal {
+        Goal::Distance { value, unit } => (DURATION_DISTANCE, centimeters(value, unit)?),
+        Goal::Race { distance } => {
+            let (value, unit) = distance.length();
+            (DURATION_DISTANCE, centimeters(value, unit)?)
+        }
+        Goal::Duration { value, unit } => (
+            DURATION_TIME,
+            fit_value(time_to_seconds(value, unit) * 1000.0)?,
+        ),
+        Goal::FirstOf {
+            distance,
+            length_unit,
+            ..
+        } => (DURATION_DISTANCE, centimeters(distance, length_unit)?),
+        Goal::Open => (DURATION_OPEN, INVALID_UINT32),
+    };
+
+    let custom = |target_type, low: u32, high: u32| (target_type, 0, low, high);
+    let alert = pace_alert(step).or_else(|| step.alerts.first());
+    let (target_type, target_value, low, high) = match alert {
+        None => (TARGET_OPEN, 0, INVALID_UINT32, INVALID_UINT32),
+        Some(Alert::HeartRate(zone)) => (
+            TARGET_HEART_RATE,
+            zone_number(zone),
+            INVALID_UINT32,
+            INVALID_UINT32,
+        ),
+        Some(Alert::HeartRateThreshold { bpm, ceiling }) => {
+            // Values up to 100 are shares of the maximum heart rate, 0% leaves the low end open
+            let low = if *ceiling { 0 } else { offset(*bpm, 100)? };
+            custom(TARGET_HEART_RATE, low, offset(*bpm, 100)?)
+        }
+        Some(Alert::HeartRateRange { min, max }) => {
+            custom(TARGET_HEART_RATE, offset(*min, 100)?, offset(*max, 100)?)
+        }
+        Some(Alert::PowerThreshold { watts }) => {
+            let watts = offset(*watts, 1000)?;
+            custom(TARGET_POWER, watts, watts)
+        }
+        Some(Alert::PowerRange { min, max }) => {
+            custom(TARGET_POWER, offset(*min, 1000)?, offset(*max, 1000)?)
+        }
+        Some(Alert::CriticalPower { percent }) => {
+            let power =
+                pace_map
+                    .power
+                    .as_ref()
+                    .ok_or_else(|| EstimateError::MissingPowerProfile {
+                        step: step.to_string(),
+                    })?;
+            let watts = offset(
+                fit_value(power.critical_power as f64 * percent / 100.0)?,
+                1000,
+            )?;
+            custom(TARGET_POWER, watts, watts)
+        }
+        Some(Alert::CadenceThreshold { spm }) => {
+            let spm = offset(*spm, 0)?;
+            custom(TARGET_CADENCE, spm, spm)
+        }
+        Some(Alert::CadenceRange { min, max }) => {
+            custom(TARGET_CADENCE, offset(*min, 0)?, offset(*max, 0)?)
+        }
+        // Paces, zone ranges and named paces are all run as speed ranges
+        Some(_) => {
+            let range = get_pace_range(pace_map, step)?;
+            let speed = |pace: &Pace| fit_value(meters(1.0, pace.unit) / pace.time * 1000.0);
+            let (a, b) = (speed(&range.min)?, speed(&range.max)?);
+            custom(TARGET_SPEED, a.min(b), a.max(b))
+        }
+    };
+
+    Ok(FitStep {
+        duration_type,
+        duration_value,
+        target_type,
+        target_value,
+        low,
+        high,
+        intensity,
+    })
+}
+
+fn zone_number(zone: &HeartRateZone) -> u32 {
+    match zone {
+        HeartRateZone::Z1 => 1,
+        HeartRateZone::Z2 => 2,
+        HeartRateZone::Z3 => 3,
+        HeartRateZone::Z4 => 4,
+        HeartRateZone::Z5 => 5,
+    }
+}
+
+struct Field {
+    number: u8,
+    base_type: u8,
+    bytes: Vec<u8>,
+}
+
+impl Field {
+    fn enumeration(number: u8, value: u8) -> Self {
+        Field {
+            number,
+            base_type: ENUM,
+            bytes: vec![value],
+        }
+    }
+
+    fn uint16(number: u8, value: u16) -> Self {
+        Field {
+            number,
+            base_type: UINT16,
+            bytes: value.to_le_bytes().to_vec(),
+        }
+    }
+
+    fn uint32(number: u8, value: u32) -> Self {
+        Field {
+            number,
+            base_type: UINT32,
+            bytes: value.to_le_bytes().to_vec(),
+        }
+    }
+
+    /// Null terminated, cut to the 255 bytes a field can hold
+    fn string(number: u8, value: &str) -> Self {
+        let mut end = value.len().min(254);
+        while !value.is_char_boundary(end) {
+            end -= 1;
+        }
+        let mut bytes = value.as_bytes()[..end].to_vec();
+        bytes.push(0);
+        Field {
+            number,
+            base_type: STRING,
+            bytes,
+        }
+    }
+}
+
+/// Number, size and base type of each field of a message
+type Definition = Vec<(u8, u8, u8)>;
+
+/// Little endian records, each local message type is defined again only when its fields change
+#[derive(Default)]
+struct FitWriter {
+    records: Vec<u8>,
+    definitions: Vec<(u8, u16, Definition)>,
+}
+
+impl FitWriter {
+    fn write_message(&mut self, local: u8, global: u16, fields: &[Field]) {
+        let definition: Definition = fields
+            .iter()
+            .map(|field| (field.number, field.bytes.len() as u8, field.base_type))
+            .collect();
+        let defined = self.definitions.iter().any(|(number, message, fields)| {
+            *number == local && *message == global && *fields == definition
+        });
+        if !defined {
+            self.records.push(0x40 | local);
+            self.records.extend([0, 0]); // Reserved, little endian
+            self.records.extend(global.to_le_bytes());
+            self.records.push(fields.len() as u8);
+            for (number, size, base_type) in &definition {
+                self.records.extend([*number, *size, *base_type]);
+            }
+            self.definitions.retain(|(number, _, _)| *number != local);
+            self.definitions.push((local, global, definition));
+        }
+
+        self.records.push(local);
+        for field in fields {
+            self.records.extend(&field.bytes);
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut file = vec![14, PROTOCOL_VERSION];
+        file.extend(PROFILE_VERSION.to_le_bytes());
+        file.extend((self.records.len() as u32).to_le_bytes());
+        file.extend(b".FIT");
+        file.extend(crc(&file).to_le_bytes());
+        file.extend(self.records);
+        file.extend(crc(&file).to_le_bytes());
+        file
+    }
+}
+
+/// A data message, with every value turned little endian
+struct Message {
+    global: u16,
+    fields: HashMap<u8, Vec<u8>>,
+}
+
+impl Message {
+    /// None when the field is missing or holds the invalid value of its type
+    fn value(&self, number: u8) -> Option<u32> {
+        let bytes = self.fields.get(&number)?;
+        if bytes.is_empty() || bytes.len() > 4 || bytes.iter().all(|byte| *byte == 0xFF) {
+            return None;
+        }
+        Some(
+            bytes
+                .iter()
+                .rev()
+                .fold(0, |value, byte| (value << 8) | *byte as u32),
+        )
+    }
+
+    fn string(&self, number: u8) -> Option<String> {
+        let bytes = self.fields.get(&number)?;
+        let end = bytes
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(bytes.len());
+        (end > 0).then(|| String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+}
+
+struct MessageDefinition {
+    global: u16,
+    big_endian: bool,
+    fields: Definition,
+    developer_size: usize, // Developer fields are skipped
+}
+
+/// Data messages of the first FIT file in `bytes`, once both checksums are verified
+fn read_messages(bytes: &[u8]) -> Result<Vec<Message>, FitError> {
+    let header_size = *bytes.first().ok_or(FitError::InvalidHeader)? as usize;
+    if header_size < 12 || bytes.len() < header_size || &bytes[8..12] != b".FIT" {
+        return Err(FitError::InvalidHeader);
+    }
+    // A header checksum of zero was not computed
+    if header_size >= 14 {
+        let checksum = u16::from_le_bytes([bytes[12], bytes[13]]);
+        if checksum != 0 && checksum != crc(&bytes[..12]) {
+            return Err(FitError::InvalidChecksum);
+        }
+    }
+    // In 64 bits, as the declared size can be anything and usize is 32 bits on wasm
+    let size = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as u64;
+    if (bytes.len() as u64) < header_size as u64 + size + 2 {
+        return Err(FitError::UnexpectedEnd);
+    }
+    let end = header_size + size as usize;
+    if crc(&bytes[..end + 2]) != 0 {
+        return Err(FitError::InvalidChecksum);
+    }
+
+    let mut definitions: HashMap<u8, MessageDefinition> = HashMap::new();
+    let mut messages = Vec::new();
+    let mut records = &bytes[header_size..end];
+    while let Some((&header, rest)) = records.split_first() {
+        records = rest;
+        let local = if header & 0x80 != 0 {
+            // Compressed timestamp header, always a data message
+            (header >> 5) & 0x03
+        } else if header & 0x40 != 0 {
+            let fixed = take(&mut records, 5)?;
+            let big_endian = fixed[1] == 1;
+            let global = match big_endian {
+                true => u16::from_be_bytes([fixed[2], fixed[3]]),
+                false => u16::from_le_bytes([fixed[2], fixed[3]]),
+            };
+            let fields = take(&mut records, 3 * fixed[4] as usize)?
+                .chunks(3)
+                .map(|field| (field[0], field[1], field[2]))
+                .collect();
+            let developer_size = match header & 0x20 != 0 {
+                true => {
+                    let count = take(&mut records, 1)?[0] as usize;
+                    take(&mut records, 3 * count)?
+                        .chunks(3)
+                        .map(|field| field[1] as usize)
+                        .sum()
+                }
+                false => 0,
+            };
+            definitions.insert(
+                header & 0x0F,
+                MessageDefinition {
+                    global,
+                    big_endian,
+                    fields,
+                    developer_size,
+                },
+            );
+            continue;
+        } else {
+            header & 0x0F
+        };
+
+        let definition = definitions
+            .get(&local)
+            .ok_or(FitError::UndefinedMessage { local })?;
+        let mut fields = HashMap::new();
+        for (number, size, base_type) in &definition.fields {
+            let mut value = take(&mut records, *size as usize)?.to_vec();
+            if definition.big_endian {
+                let width = match base_type & 0x1F {
+                    0x03 | 0x04 | 0x0B => 2,
+                    0x05 | 0x06 | 0x08 | 0x0C => 4,
+                    0x09 | 0x0E | 0x0F | 0x10 => 8,
+                    _ => 1,
+                };
+                value.chunks_mut(width).for_each(|chunk| chunk.reverse());
+            }
+            fields.insert(*number, value);
+        }
+        take(&mut records, definition.developer_size)?;
+        messages.push(Message {
+            global: definition.global,
+            fields,
+        });
+    }
+    Ok(messages)
+}
+
+fn take<'a>(records: &mut &'a [u8], size: usize) -> Result<&'a [u8], FitError> {
+    let (taken, rest) = records
+        .split_at_checked(size)
+        .ok_or(FitError::UnexpectedEnd)?;
+    *records = rest;
+    Ok(taken)
+}
+
+fn crc(bytes: &[u8]) -> u16 {
+    const TABLE: [u16; 16] = [
+        0x0000, 0xCC01, 0xD801, 0x1400, 0xF001, 0x3C00, 0x2800, 0xE401, 0xA001, 0x6C00, 0x7800,
+        0xB401, 0x5000, 0x9C01, 0x8801, 0x4400,
+    ];
+    bytes.iter().fold(0, |crc, byte| {
+        let crc = (crc >> 4) ^ TABLE[(crc & 0xF) as usize] ^ TABLE[(byte & 0xF) as usize];
+        (crc >> 4) ^ TABLE[(crc & 0xF) as usize] ^ TABLE[(byte >> 4) as usize]
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,524 @@
+use std::collections::HashMap;
+
+use super::*;
+use crate::fixtures::PACE_MAP;
+use crate::parser::parse_workout;
+use crate::printer::to_dsl;
+use wasm_bindgen_test::*;
+
+/// A decoded data message, values are kept as raw little endian bytes
+struct Message {
+    global: u16,
+    fields: HashMap<u8, Vec<u8>>,
+}
+
+impl Message {
+    fn value(&self, number: u8) -> u32 {
+        let bytes = &self.fields[&number];
+        bytes
+            .iter()
+            .rev()
+            .fold(0, |value, byte| (value << 8) | *byte as u32)
+    }
+
+    fn string(&self, number: u8) -> String {
+        let bytes = &self.fields[&number];
+        let end = bytes.iter().position(|byte| *byte == 0).unwrap();
+        String::from_utf8(bytes[..end].to_vec()).unwrap()
+    }
+}
+
+/// Reads back the messages of a file, checking both CRCs on the way
+fn decode(file: &[u8]) -> Vec<Message> {
+    assert_eq!(file[0], 14);
+    assert_eq!(&file[8..12], b".FIT");
+    assert_eq!(crc(&file[..14]), 0);
+    assert_eq!(crc(file), 0);
+    let size = u32::from_le_bytes(file[4..8].try_into().unwrap()) as usize;
+    assert_eq!(file.len(), 14 + size + 2);
+
+    let mut definitions: HashMap<u8, (u16, Vec<(u8, usize)>)> = HashMap::new();
+    let mut messages = Vec::new();
+    let mut records = &file[14..14 + size];
+    while let Some((header, rest)) = records.split_first() {
+        let local = header & 0x0F;
+        if header & 0x40 != 0 {
+            assert_eq!(rest[1], 0, "big endian definition");
+            let global = u16::from_le_bytes([rest[2], rest[3]]);
+            let count = rest[4] as usize;
+            let fields = rest[5..5 + 3 * count]
+                .chunks(3)
+                .map(|field| (field[0], field[1] as usize))
+                .collect();
+            definitions.insert(local, (global, fields));
+            records = &rest[5 + 3 * count..];
+        } else {
+            let (global, definition) = &definitions[&local];
+            let mut fields = HashMap::new();
+            let mut offset = 0;
+            for (number, size) in definition {
+                fields.insert(*number, rest[offset..offset + size].to_vec());
+                offset += size;
+            }
+            messages.push(Message {
+                global: *global,
+                fields,
+            });
+            records = &rest[offset..];
+        }
+    }
+    messages
+}
+
+fn workout_steps(input: &str) -> Vec<Message> {
+    let file = to_fit(&PACE_MAP, &parse_workout(input).unwrap(), "Test").unwrap();
+    decode(&file)
+        .into_iter()
+        .filter(|message| message.global == WORKOUT_STEP)
+        .collect()
+}
+
+/// Duration type and value, target type, value, low and high, and intensity of a step
+fn summary(step: &Message) -> (u32, u32, u32, u32, u32, u32, u32) {
+    (
+        step.value(1),
+        step.value(2),
+        step.value(3),
+        step.value(4),
+        step.value(5),
+        step.value(6),
+        step.value(7),
+    )
+}
+
+const NONE: u32 = INVALID_UINT32;
+const NO_ENUM: u32 = INVALID_ENUM as u32;
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_crc() {
+    assert_eq!(crc(b""), 0);
+    assert_eq!(crc(b"123456789"), 0xBB3D);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_header() {
+    let workout = parse_workout("3 x (400m @z4 + 200m recovery)").unwrap();
+    let file = to_fit(&PACE_MAP, &workout, "Track 400s").unwrap();
+    let messages = decode(&file);
+
+    assert_eq!(messages[0].global, FILE_ID);
+    assert_eq!(messages[0].value(0), FILE_TYPE_WORKOUT as u32);
+    assert_eq!(messages[1].global, WORKOUT);
+    assert_eq!(messages[1].value(4), SPORT_RUNNING as u32);
+    assert_eq!(messages[1].value(6), 3);
+    assert_eq!(messages[1].string(8), "Track 400s");
+    assert_eq!(messages.len(), 5);
+    for (index, step) in messages[2..].iter().enumerate() {
+        assert_eq!(step.value(254), index as u32);
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_workout_steps() {
+    let steps =
+        workout_steps("1 mile warmup + 4 x (1 km @z4 + 90 sec recovery) + 10 min @z1 cooldown");
+    assert_eq!(
+        steps.iter().map(summary).collect::<Vec<_>>(),
+        vec![
+            (1, 160934, 2, 0, NONE, NONE, 2),
+            (1, 100000, 1, 4, NONE, NONE, 0),
+            (0, 90000, 2, 0, NONE, NONE, 4),
+            (6, 1, NO_ENUM, 4, NONE, NONE, NO_ENUM),
+            (0, 600000, 1, 1, NONE, NONE, 3),
+        ]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_nested_repeats() {
+    let steps = workout_steps("2 km + 2 x (3 x (200m + 200m recovery) + 3 min recovery)");
+    let durations: Vec<_> = steps
+        .iter()
+        .map(|step| (step.value(1), step.value(2), step.value(4)))
+        .collect();
+    assert_eq!(
+        durations,
+        vec![
+            (1, 200000, 0),
+            (1, 20000, 0),
+            (1, 20000, 0),
+            (6, 1, 3),
+            (0, 180000, 0),
+            (6, 1, 2),
+        ]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_goals() {
+    let steps = workout_steps("5k + open + 1 mile or 8 min + 1:30:00");
+    let durations: Vec<_> = steps
+        .iter()
+        .map(|step| (step.value(1), step.value(2)))
+        .collect();
+    assert_eq!(
+        durations,
+        vec![(1, 500000), (5, NONE), (1, 160934), (0, 5400000)]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_targets() {
+    let steps = workout_steps(
+        "1 km @4:00/km + 1 km @z2 @5:00-4:00/km + 1 km @z2-z3 + 1 km @12 km/h \
+         + 1 km @150bpm + 1 km @<160bpm + 1 km @140-150bpm \
+         + 1 km @250W + 1 km @90% CP + 1 km @180spm",
+    );
+    let targets: Vec<_> = steps
+        .iter()
+        .map(|step| (step.value(3), step.value(4), step.value(5), step.value(6)))
+        .collect();
+    assert_eq!(
+        targets,
+        vec![
+            (0, 0, 4167, 4167),
+            (0, 0, 3333, 4167),
+            (0, 0, 2667, 4167),
+            (0, 0, 3333, 3333),
+            (1, 0, 250, 250),
+            (1, 0, 0, 260),
+            (1, 0, 240, 250),
+            (4, 0, 1250, 1250),
+            (4, 0, 1225, 1225),
+            (3, 0, 180, 180),
+        ]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_missing_pace() {
+    let workout = parse_workout("1 km @z4-z5").unwrap();
+    assert!(matches!(
+        to_fit(&PACE_MAP, &workout, "Test"),
+        Err(FitError::Estimate {
+            error: EstimateError::MissingZone { .. }
+        })
+    ));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_out_of_range() {
+    let step = |goal: Goal, alert: Option<Alert>| WorkoutStep {
+        goal,
+        alerts: alert.into_iter().collect(),
+    };
+    let km = |value: f64| Goal::Distance {
+        value,
+        unit: LengthUnit::Kilometers,
+    };
+    let max = u32::MAX;
+    for step in [
+        step(km(1.0), Some(Alert::PowerThreshold { watts: max })),
+        step(km(1.0), Some(Alert::PowerRange { min: 200, max })),
+        step(
+            km(1.0),
+            Some(Alert::HeartRateThreshold {
+                bpm: max - 50,
+                ceiling: false,
+            }),
+        ),
+        step(km(1.0), Some(Alert::CadenceThreshold { spm: max })),
+        step(
+            km(1.0),
+            Some(Alert::PaceThreshold(Pace {
+                time: 0.0,
+                unit: LengthUnit::Kilometers,
+            })),
+        ),
+        step(km(1e8), None),
+    ] {
+        let workout = Workout {
+            warmup: Some(step),
+            intervals: vec![],
+            cooldown: None,
+        };
+        assert!(matches!(
+            to_fit(&PACE_MAP, &workout, "Test"),
+            Err(FitError::OutOfRange { .. })
+        ));
+    }
+
+    let mut workout = parse_workout("3 x (1 km)").unwrap();
+    workout.intervals[0].repeats = Some(max);
+    assert!(matches!(
+        to_fit(&PACE_MAP, &workout, "Test"),
+        Err(FitError::OutOfRange { .. })
+    ));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_too_many_steps() {
+    let step = parse_workout("1 km").unwrap().intervals[0].clone();
+    let workout = Workout {
+        warmup: None,
+        intervals: vec![step; MAX_STEPS + 1],
+        cooldown: None,
+    };
+    assert_eq!(
+        to_fit(&PACE_MAP, &workout, "Test"),
+        Err(FitError::TooManySteps)
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_long_name() {
+    let workout = parse_workout("1 km").unwrap();
+    let name = "é".repeat(200);
+    let messages = decode(&to_fit(&PACE_MAP, &workout, &name).unwrap());
+    assert_eq!(messages[1].string(8), "é".repeat(127));
+}
+
+fn fit_file(file_type: u8, steps: &[FitStep]) -> Vec<u8> {
+    let mut writer = FitWriter::default();
+    writer.write_message(0, FILE_ID, &[Field::enumeration(0, file_type)]);
+    for (index, step) in steps.iter().enumerate() {
+        writer.write_message(
+            1,
+            WORKOUT_STEP,
+            &[
+                Field::uint16(254, index as u16),
+                Field::enumeration(1, step.duration_type),
+                Field::uint32(2, step.duration_value),
+                Field::enumeration(3, step.target_type),
+                Field::uint32(4, step.target_value),
+                Field::uint32(5, step.low),
+                Field::uint32(6, step.high),
+                Field::enumeration(7, step.intensity),
+            ],
+        );
+    }
+    writer.finish()
+}
+
+fn fit_step(duration: (u8, u32), target: (u8, u32, u32, u32), intensity: u8) -> FitStep {
+    FitStep {
+        duration_type: duration.0,
+        duration_value: duration.1,
+        target_type: target.0,
+        target_value: target.1,
+        low: target.2,
+        high: target.3,
+        intensity,
+    }
+}
+
+fn round_trip(input: &str) -> FitWorkout {
+    let file = to_fit(&PACE_MAP, &parse_workout(input).unwrap(), "Test").unwrap();
+    from_fit(&file).unwrap()
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_round_trip() {
+    for input in [
+        "1 mile @z1 warmup + 4 x (1 km @z4 + 90 sec recovery) + 10 min cooldown",
+        "2 x (3 x (200m @4:00/km + 200m recovery) + 3 min recovery) + 1.5 mile",
+        "1 km @150bpm + 1 km @<160bpm + 1 km @140-150bpm + 1 km @5:00-4:30/km",
+        "1 km @250W + 1 km @200-250W + 1 km @180spm + 1 km @170-180spm + open",
+    ] {
+        let imported = round_trip(input);
+        assert_eq!(imported.name, Some("Test".to_string()));
+        assert_eq!(imported.workout, parse_workout(input).unwrap(), "{}", input);
+        assert!(imported.warnings.is_empty());
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_round_trip_resolves() {
+    let imported = round_trip("5k + 1 mile or 8 min + 1:30:00 + 800m @z2-z3 + 20 min @12 km/h");
+    assert_eq!(
+        to_dsl(&imported.workout),
+        "5 km + 1 mi + 90 min + 800 m @6:15-4:00/km + 20 min @5:00/km"
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_unsupported() {
+    let open = (TARGET_OPEN, 0, NONE, NONE);
+    let file = fit_file(
+        FILE_TYPE_WORKOUT,
+        &[
+            // Share of the maximum heart rate
+            fit_step((DURATION_TIME, 60000), (TARGET_HEART_RATE, 0, 80, 90), 0),
+            // Calories, and a power zone
+            fit_step((4, 100), (TARGET_POWER, 3, NONE, NONE), INTENSITY_REST),
+            // Grade
+            fit_step((DURATION_DISTANCE, 40000), (5, 0, 2, 4), INTENSITY_WARMUP),
+            // Until a time
+            fit_step((7, 600000), open, 0xFF),
+        ],
+    );
+    let imported = from_fit(&file).unwrap();
+    assert_eq!(imported.name, None);
+    assert_eq!(to_dsl(&imported.workout), "1 min + open recovery + 400 m");
+    assert_eq!(
+        imported
+            .warnings
+            .iter()
+            .map(|warning| (warning.severity, warning.code, warning.message.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                Severity::Warning,
+                ErrorCode::UnsupportedTarget,
+                "Step 1 has an unsupported target, it is dropped"
+            ),
+            (
+                Severity::Warning,
+                ErrorCode::UnsupportedDuration,
+                "Step 2 ends on an unsupported condition, it is left open"
+            ),
+            (
+                Severity::Warning,
+                ErrorCode::UnsupportedTarget,
+                "Step 2 has an unsupported target, it is dropped"
+            ),
+            (
+                Severity::Warning,
+                ErrorCode::UnsupportedTarget,
+                "Step 3 has an unsupported target, it is dropped"
+            ),
+            (
+                Severity::Warning,
+                ErrorCode::UnsupportedDuration,
+                "Step 4 repeats on an unsupported condition, its steps run once"
+            ),
+        ]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_heart_rate_ceiling() {
+    let heart_rate = |low, high| {
+        let file = fit_file(
+            FILE_TYPE_WORKOUT,
+            &[fit_step(
+                (DURATION_TIME, 60000),
+                (TARGET_HEART_RATE, 0, low, high),
+                0,
+            )],
+        );
+        from_fit(&file).unwrap()
+    };
+    assert_eq!(to_dsl(&heart_rate(0, 245).workout), "1 min @<145bpm");
+    assert_eq!(to_dsl(&heart_rate(230, 245).workout), "1 min @130-145bpm");
+
+    // 100 is the whole maximum heart rate, not 0 bpm
+    let imported = heart_rate(100, 245);
+    assert_eq!(to_dsl(&imported.workout), "1 min");
+    assert_eq!(imported.warnings[0].code, ErrorCode::UnsupportedTarget);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_invalid_files() {
+    assert_eq!(from_fit(b""), Err(FitError::InvalidHeader));
+    assert_eq!(from_fit(b"not a fit file"), Err(FitError::InvalidHeader));
+
+    let file = fit_file(FILE_TYPE_WORKOUT, &[]);
+    assert_eq!(from_fit(&file), Err(FitError::NotAWorkout));
+    assert_eq!(from_fit(&file[..20]), Err(FitError::UnexpectedEnd));
+
+    // A size that wraps around when added to the header size on 32 bits
+    let mut file = fit_file(FILE_TYPE_WORKOUT, &[]);
+    file[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+    let checksum = crc(&file[..12]);
+    file[12..14].copy_from_slice(&checksum.to_le_bytes());
+    assert_eq!(from_fit(&file), Err(FitError::UnexpectedEnd));
+
+    let step = fit_step((DURATION_OPEN, NONE), (TARGET_OPEN, 0, NONE, NONE), 0);
+    let mut file = fit_file(FILE_TYPE_WORKOUT, &[step]);
+    let last = file.len() - 3;
+    file[last] ^= 1;
+    assert_eq!(from_fit(&file), Err(FitError::InvalidChecksum));
+
+    // Activities are FIT files too
+    let step = fit_step((DURATION_OPEN, NONE), (TARGET_OPEN, 0, NONE, NONE), 0);
+    assert_eq!(from_fit(&fit_file(4, &[step])), Err(FitError::NotAWorkout));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_invalid_repeat() {
+    let file = fit_file(
+        FILE_TYPE_WORKOUT,
+        &[
+            fit_step((DURATION_TIME, 60000), (TARGET_OPEN, 0, NONE, NONE), 0),
+            fit_step((DURATION_REPEAT, 5), (TARGET_OPEN, 3, NONE, NONE), 0),
+        ],
+    );
+    assert_eq!(
+        from_fit(&file),
+        Err(FitError::InvalidRepeat { step: 2, target: 6 })
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_validates_workout() {
+    let open = (TARGET_OPEN, 0, NONE, NONE);
+    let repeated = |count: u32| {
+        fit_file(
+            FILE_TYPE_WORKOUT,
+            &[
+                fit_step((DURATION_TIME, 60000), open, 0),
+                fit_step((DURATION_REPEAT, 0), (TARGET_OPEN, count, NONE, NONE), 0),
+            ],
+        )
+    };
+    assert_eq!(
+        to_dsl(&from_fit(&repeated(4)).unwrap().workout),
+        "4 x (1 min)"
+    );
+    for count in [0, u32::MAX - 1] {
+        assert!(matches!(
+            from_fit(&repeated(count)),
+            Err(FitError::InvalidWorkout { .. })
+        ));
+    }
+
+    // Warnings of the validator come with the import warnings
+    let file = fit_file(
+        FILE_TYPE_WORKOUT,
+        &[fit_step((DURATION_TIME, 9 * 3600 * 1000), open, 0)],
+    );
+    let imported = from_fit(&file).unwrap();
+    assert_eq!(
+        imported
+            .warnings
+            .iter()
+            .map(|warning| warning.code)
+            .collect::<Vec<_>>(),
+        vec![ErrorCode::LongStep]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_fit_warmup_only() {
+    let open = (TARGET_OPEN, 0, NONE, NONE);
+    let warmup = || fit_step((DURATION_TIME, 600000), open, INTENSITY_WARMUP);
+    let cooldown = || fit_step((DURATION_TIME, 300000), open, INTENSITY_COOLDOWN);
+
+    // A lone warmup or cooldown stays an interval, the text form needs one
+    for (steps, expected) in [
+        (vec![warmup()], "10 min"),
+        (vec![cooldown()], "5 min"),
+        (vec![warmup(), cooldown()], "10 min warmup + 5 min"),
+    ] {
+        let imported = from_fit(&fit_file(FILE_TYPE_WORKOUT, &steps)).unwrap();
+        assert_eq!(imported.warnings, vec![]);
+        assert_eq!(to_dsl(&imported.workout), expected);
+        assert!(parse_workout(expected).is_ok());
+    }
+
+    assert!(matches!(
+        from_fit(&fit_file(FILE_TYPE_WORKOUT, &[])),
+        Err(FitError::NotAWorkout)
+    ));
+}
@@ -0,0 +1,53 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+
+use crate::models::*;
+
+fn minutes_per_km(minutes: f64) -> Pace {
+    Pace {
+        time: minutes * 60.0,
+        unit: LengthUnit::Kilometers,
+    }
+}
+
+lazy_static! {
+    /// Z2 and Z3 only, with critical power held at 4:00/km
+    pub(crate) static ref PACE_MAP: PaceMap = PaceMap {
+        zones: HashMap::from([
+            (
+                HeartRateZone::Z2,
+                PaceRange {
+                    min: minutes_per_km(6.25),
+                    max: minutes_per_km(5.0),
+                },
+            ),
+            (
+                HeartRateZone::Z3,
+                PaceRange {
+                    min: minutes_per_km(5.0),
+                    max: minutes_per_km(4.0),
+                },
+            ),
+        ]),
+        default: HeartRateZone::Z2,
+        fallback: ZoneFallback::Error,
+        heart_rates: vec![],
+        power: Some(PowerProfile {
+            critical_power: 250,
+            critical_pace: minutes_per_km(4.0),
+        }),
+        paces: HashMap::new(),
+    };
+}
+
+/// A sample without any reading
+pub(crate) fn sample(elapsed: f64, distance: f64) -> Sample {
+    Sample {
+        elapsed,
+        distance,
+        heart_rate: None,
+        pace: None,
+        power: None,
+        cadence: None,
+    }
+}
@@ -1,22 +1,163 @@
 #![cfg(feature = "ios")]
 
+use std::sync::{Arc, Mutex};
+
+use crate::error::{DashError, Diagnostic, EstimateError, ExpandError, FitError, PaceMapError};
 use crate::models;
 
 uniffi::setup_scaffolding!();
 
 #[derive(uniffi::Error, thiserror::Error, Debug)]
-#[uniffi(flat_error)]
 pub enum UniffiError {
-    #[error("Parsing error: {0}")]
-    Err(String),
+    #[error("Parsing error: {error}")]
+    Parse { error: DashError },
 }
 
 #[uniffi::export]
 pub fn get_workout(input: &str) -> Result<models::Workout, UniffiError> {
-    crate::parser::parse_workout(input).map_err(|e| UniffiError::Err(e.to_string()))
+    crate::parser::parse_workout(input).map_err(|error| UniffiError::Parse { error })
+}
+
+#[uniffi::export]
+pub fn lint_workout(input: &str) -> Result<Vec<Diagnostic>, UniffiError> {
+    crate::parser::lint_workout(input).map_err(|error| UniffiError::Parse { error })
+}
+
+#[uniffi::export]
+pub fn validate(workout: &models::Workout) -> Vec<Diagnostic> {
+    crate::validate::validate(workout)
+}
+
+#[uniffi::export]
+pub fn expand_workout(workout: &models::Workout) -> Result<Vec<models::ExpandedStep>, ExpandError> {
+    workout.expand()
 }
 
 #[uniffi::export]
-pub fn get_mileage(pace_map: &models::PaceMap, workout: &models::Workout) -> models::Mileage {
+pub fn get_mileage(
+    pace_map: &models::PaceMap,
+    workout: &models::Workout,
+) -> Result<models::Mileage, EstimateError> {
     crate::mileage::get_mileage(pace_map, workout)
 }
+
+#[uniffi::export]
+pub fn get_duration(
+    pace_map: &models::PaceMap,
+    workout: &models::Workout,
+) -> Result<models::WorkoutDuration, EstimateError> {
+    crate::duration::get_duration(pace_map, workout)
+}
+
+#[uniffi::export]
+pub fn get_breakdown(
+    pace_map: &models::PaceMap,
+    workout: &models::Workout,
+) -> Result<models::Breakdown, EstimateError> {
+    crate::breakdown::get_breakdown(pace_map, workout)
+}
+
+#[uniffi::export]
+pub fn get_pace_map(race: &models::Goal, time: f64) -> Result<models::PaceMap, PaceMapError> {
+    crate::pace_map::get_pace_map(race, time)
+}
+
+#[uniffi::export]
+pub fn to_fit(
+    pace_map: &models::PaceMap,
+    workout: &models::Workout,
+    name: &str,
+) -> Result<Vec<u8>, FitError> {
+    crate::fit::to_fit(pace_map, workout, name)
+}
+
+#[uniffi::export]
+pub fn from_fit(bytes: &[u8]) -> Result<models::FitWorkout, FitError> {
+    crate::fit::from_fit(bytes)
+}
+
+#[uniffi::export]
+pub fn to_dsl(workout: &models::Workout) -> String {
+    crate::printer::to_dsl(workout)
+}
+
+#[derive(uniffi::Object)]
+pub struct WorkoutSession {
+    session: Mutex<crate::session::Session>,
+}
+
+#[uniffi::export]
+impl WorkoutSession {
+    #[uniffi::constructor(default(config = None))]
+    pub fn new(
+        workout: &models::Workout,
+        pace_map: models::PaceMap,
+        config: Option<models::EvaluatorConfig>,
+    ) -> Result<Arc<Self>, ExpandError> {
+        let session = crate::session::Session::new(workout, pace_map, config.unwrap_or_default())?;
+        Ok(Arc::new(WorkoutSession {
+            session: Mutex::new(session),
+        }))
+    }
+
+    pub fn state(&self) -> models::SessionState {
+        self.session.lock().unwrap().state()
+    }
+
+    pub fn current_step(&self) -> Option<models::ExpandedStep> {
+        self.session.lock().unwrap().current_step().cloned()
+    }
+
+    pub fn start(&self) -> Vec<models::SessionEvent> {
+        self.session.lock().unwrap().start()
+    }
+
+    pub fn push_sample(&self, sample: models::Sample) -> Vec<models::SessionEvent> {
+        self.session.lock().unwrap().push_sample(sample)
+    }
+
+    pub fn lap(&self) -> Vec<models::SessionEvent> {
+        self.session.lock().unwrap().lap()
+    }
+
+    pub fn skip(&self) -> Vec<models::SessionEvent> {
+        self.session.lock().unwrap().skip()
+    }
+
+    pub fn pause(&self) -> Vec<models::SessionEvent> {
+        self.session.lock().unwrap().pause()
+    }
+
+    pub fn resume(&self) -> Vec<models::SessionEvent> {
+        self.session.lock().unwrap().resume()
+    }
+}
+
+#[derive(uniffi::Object)]
+pub struct AlertEvaluator {
+    evaluator: Mutex<crate::evaluator::AlertEvaluator>,
+}
+
+#[uniffi::export]
+impl AlertEvaluator {
+    #[uniffi::constructor(default(config = None))]
+    pub fn new(
+        alert: &models::Alert,
+        pace_map: &models::PaceMap,
+        config: Option<models::EvaluatorConfig>,
+    ) -> Arc<Self> {
+        let evaluator =
+            crate::evaluator::AlertEvaluator::new(alert, pace_map, config.unwrap_or_default());
+        Arc::new(AlertEvaluator {
+            evaluator: Mutex::new(evaluator),
+        })
+    }
+
+    pub fn status(&self) -> Option<models::AlertStatus> {
+        self.evaluator.lock().unwrap().status()
+    }
+
+    pub fn push(&self, sample: &models::Sample) -> Option<models::AlertStatus> {
+        self.evaluator.lock().unwrap().push(sample)
+    }
+}
@@ -1,6 +1,18 @@
+mod breakdown;
+mod duration;
+mod error;
+mod evaluator;
+mod expand;
+mod fit;
+#[cfg(test)]
+mod fixtures;
 mod mileage;
 mod models;
+mod pace_map;
 mod parser;
+mod printer;
+mod session;
+mod validate;
 
 #[cfg(feature = "ios")]
 mod ios;
@@ -11,5 +23,13 @@ pub use ios::*;
 #[cfg(feature = "wasm")]
 mod wasm;
 
+pub use breakdown::get_breakdown;
+pub use duration::get_duration;
+pub use evaluator::AlertEvaluator;
+pub use fit::{from_fit, to_fit};
 pub use mileage::get_mileage;
-pub use parser::parse_workout;
+pub use pace_map::get_pace_map;
+pub use parser::{lint_workout, parse_workout};
+pub use printer::to_dsl;
+pub use session::Session;
+pub use validate::validate;
@@ -1,33 +1,63 @@
+use crate::error::EstimateError;
 use crate::models::*;
+use std::collections::HashMap;
 
-pub fn get_mileage(pace_map: &PaceMap, workout: &Workout) -> Mileage {
+pub fn get_mileage(pace_map: &PaceMap, workout: &Workout) -> Result<Mileage, EstimateError> {
     let mut min = 0.0;
     let mut max = 0.0;
+    let mut open = false;
 
     if let Some(warmup) = &workout.warmup {
-        let warmup_mileage = get_workout_step_mileage(pace_map, &warmup);
+        let warmup_mileage = get_workout_step_mileage(pace_map, warmup)?;
         min += warmup_mileage.min;
         max += warmup_mileage.max;
+        open |= warmup_mileage.open;
     }
 
     for interval in &workout.intervals {
-        for step in &interval.steps {
-            let step_mileage = get_workout_step_mileage(pace_map, &step.step);
-            min += step_mileage.min * interval.repeats.unwrap_or(1) as f64;
-            max += step_mileage.max * interval.repeats.unwrap_or(1) as f64;
-        }
+        let interval_mileage = get_interval_block_mileage(pace_map, interval)?;
+        min += interval_mileage.min;
+        max += interval_mileage.max;
+        open |= interval_mileage.open;
     }
 
     if let Some(cooldown) = &workout.cooldown {
-        let cooldown_mileage = get_workout_step_mileage(pace_map, &cooldown);
+        let cooldown_mileage = get_workout_step_mileage(pace_map, cooldown)?;
         min += cooldown_mileage.min;
         max += cooldown_mileage.max;
+        open |= cooldown_mileage.open;
+    }
+
+    Ok(Mileage { min, max, open })
+}
+
+fn get_interval_block_mileage(
+    pace_map: &PaceMap,
+    interval: &IntervalBlock,
+) -> Result<Mileage, EstimateError> {
+    let mut min = 0.0;
+    let mut max = 0.0;
+    let mut open = false;
+
+    for rep in &interval.steps {
+        let rep_mileage = match rep {
+            IntervalRep::Step(step) => get_workout_step_mileage(pace_map, &step.step)?,
+            IntervalRep::Block(block) => get_interval_block_mileage(pace_map, block)?,
+        };
+        min += rep_mileage.min;
+        max += rep_mileage.max;
+        open |= rep_mileage.open;
     }
 
-    Mileage { min, max }
+    let repeats = interval.repeats.unwrap_or(1) as f64;
+    Ok(Mileage {
+        min: min * repeats,
+        max: max * repeats,
+        open,
+    })
 }
 
-fn length_unit_to_miles(unit: LengthUnit) -> f64 {
+pub(crate) fn length_unit_to_miles(unit: LengthUnit) -> f64 {
     match unit {
         LengthUnit::Miles => 1.0,
         LengthUnit::Yards => 0.000568182,
@@ -41,7 +71,7 @@ fn distance_to_miles(distance: f64, unit: LengthUnit) -> f64 {
     distance * length_unit_to_miles(unit)
 }
 
-fn time_to_seconds(time: f64, unit: TimeUnit) -> f64 {
+pub(crate) fn time_to_seconds(time: f64, unit: TimeUnit) -> f64 {
     match unit {
         TimeUnit::Seconds => time,
         TimeUnit::Minutes => time * 60.0,
@@ -51,38 +81,292 @@ fn time_to_seconds(time: f64, unit: TimeUnit) -> f64 {
 
 fn time_unit_to_miles(pace_range: &PaceRange, value: f64, unit: TimeUnit) -> Mileage {
     let time_in_seconds = time_to_seconds(value, unit);
-    return Mileage {
-        min: time_in_seconds / (pace_range.min.time / length_unit_to_miles(pace_range.min.unit)),
-        max: time_in_seconds / (pace_range.max.time / length_unit_to_miles(pace_range.max.unit)),
+    let miles = |pace: &Pace| time_in_seconds / (pace.time / length_unit_to_miles(pace.unit));
+
+    // Ranges may be written fast-to-slow or slow-to-fast
+    let (first, second) = (miles(&pace_range.min), miles(&pace_range.max));
+    Mileage {
+        min: first.min(second),
+        max: first.max(second),
+        open: false,
+    }
+}
+
+pub(crate) fn get_workout_step_mileage(
+    pace_map: &PaceMap,
+    step: &WorkoutStep,
+) -> Result<Mileage, EstimateError> {
+    let exact = |value: f64, unit: LengthUnit| Mileage {
+        min: distance_to_miles(value, unit),
+        max: distance_to_miles(value, unit),
+        open: false,
     };
+    match step.goal {
+        Goal::Distance { value, unit } => Ok(exact(value, unit)),
+        Goal::Race { distance } => {
+            let (value, unit) = distance.length();
+            Ok(exact(value, unit))
+        }
+        Goal::Duration { value, unit } => {
+            let pace_range = get_pace_range(pace_map, step)?;
+            Ok(time_unit_to_miles(&pace_range, value, unit))
+        }
+        Goal::FirstOf {
+            distance,
+            length_unit,
+            duration,
+            time_unit,
+        } => {
+            let pace_range = get_pace_range(pace_map, step)?;
+            let timed = time_unit_to_miles(&pace_range, duration, time_unit);
+            let miles = distance_to_miles(distance, length_unit);
+            Ok(Mileage {
+                min: timed.min.min(miles),
+                max: timed.max.min(miles),
+                open: false,
+            })
+        }
+        Goal::Open => Ok(Mileage {
+            min: 0.0,
+            max: 0.0,
+            open: true,
+        }),
+    }
+}
+
+pub(crate) fn get_pace_range(
+    pace_map: &PaceMap,
+    step: &WorkoutStep,
+) -> Result<PaceRange, EstimateError> {
+    match pace_alert(step) {
+        Some(Alert::PaceThreshold(pace)) => Ok(PaceRange {
+            min: pace.clone(),
+            max: pace.clone(),
+        }),
+        Some(Alert::PaceRange { min, max }) => Ok(PaceRange {
+            min: min.clone(),
+            max: max.clone(),
+        }),
+        Some(Alert::SpeedThreshold(speed)) => Ok(PaceRange {
+            min: speed_to_pace(speed),
+            max: speed_to_pace(speed),
+        }),
+        Some(Alert::SpeedRange { min, max }) => Ok(PaceRange {
+            min: speed_to_pace(min),
+            max: speed_to_pace(max),
+        }),
+        Some(Alert::HeartRate(zone)) => get_zone_pace_range(pace_map, zone, step),
+        Some(Alert::HeartRateZoneRange { min, max }) => Ok(PaceRange {
+            min: get_zone_pace_range(pace_map, min, step)?.min,
+            max: get_zone_pace_range(pace_map, max, step)?.max,
+        }),
+        Some(Alert::TrainingPace(pace)) => get_training_pace_range(pace_map, pace, step),
+        Some(Alert::HeartRateThreshold { bpm, ceiling }) => {
+            // Below a ceiling, anything from the slowest known heart rate is fine
+            let min_bpm = match ceiling {
+                true => pace_map.heart_rates.iter().map(|hr| hr.bpm).min(),
+                false => Some(*bpm),
+            };
+            get_heart_rate_pace_range(pace_map, min_bpm.unwrap_or(*bpm), *bpm, step)
+        }
+        Some(Alert::HeartRateRange { min, max }) => {
+            get_heart_rate_pace_range(pace_map, *min, *max, step)
+        }
+        Some(Alert::PowerThreshold { watts }) => {
+            get_power_pace_range(pace_map, *watts as f64, *watts as f64, step)
+        }
+        Some(Alert::PowerRange { min, max }) => {
+            get_power_pace_range(pace_map, *min as f64, *max as f64, step)
+        }
+        Some(Alert::CriticalPower { percent }) => {
+            let watts = pace_map
+                .power
+                .as_ref()
+                .map(|power| power.critical_power as f64 * percent / 100.0)
+                .unwrap_or(0.0);
+            get_power_pace_range(pace_map, watts, watts, step)
+        }
+        Some(Alert::CadenceThreshold { .. } | Alert::CadenceRange { .. }) | None => {
+            get_zone_pace_range(pace_map, &pace_map.default, step)
+        }
+    }
+}
+
+pub(crate) fn speed_to_pace(speed: &Speed) -> Pace {
+    Pace {
+        time: 3600.0 / speed.value,
+        unit: speed_unit_to_length_unit(speed.unit),
+    }
+}
+
+pub(crate) fn speed_unit_to_length_unit(unit: SpeedUnit) -> LengthUnit {
+    match unit {
+        SpeedUnit::KilometersPerHour => LengthUnit::Kilometers,
+        SpeedUnit::MilesPerHour => LengthUnit::Miles,
+    }
+}
+
+/// The alert that drives the pace estimate of a step, the most direct one wins:
+/// pace or speed, then power, then heart rate. Cadence says nothing about pace, so a step
+/// with only a cadence alert runs in the default zone like a step without alerts.
+pub(crate) fn pace_alert(step: &WorkoutStep) -> Option<&Alert> {
+    [AlertKind::Pace, AlertKind::Power, AlertKind::HeartRate]
+        .iter()
+        .find_map(|kind| step.alerts.iter().find(|alert| alert.kind() == *kind))
 }
 
-fn get_workout_step_mileage(pace_map: &PaceMap, step: &WorkoutStep) -> Mileage {
-    match &step.goal {
-        Goal::Distance { value, unit } => Mileage {
-            min: distance_to_miles(*value, *unit),
-            max: distance_to_miles(*value, *unit),
+fn get_heart_rate_pace_range(
+    pace_map: &PaceMap,
+    min_bpm: u32,
+    max_bpm: u32,
+    step: &WorkoutStep,
+) -> Result<PaceRange, EstimateError> {
+    match (
+        heart_rate_to_pace(pace_map, min_bpm),
+        heart_rate_to_pace(pace_map, max_bpm),
+    ) {
+        (Some(min), Some(max)) => Ok(PaceRange { min, max }),
+        _ => match pace_map.fallback {
+            ZoneFallback::Error => Err(EstimateError::MissingHeartRates {
+                step: step.to_string(),
+            }),
+            _ => get_zone_pace_range(pace_map, &pace_map.default, step),
         },
-        Goal::Duration { value, unit } => {
-            let pace_range = match &step.alert {
-                Some(Alert::PaceThreshold(pace)) => &PaceRange {
-                    min: pace.clone(),
-                    max: pace.clone(),
-                },
-                Some(Alert::PaceRange { min, max }) => &PaceRange {
-                    min: min.clone(),
-                    max: max.clone(),
-                },
-                Some(Alert::HeartRate(zone)) => {
-                    let pace_range = pace_map.zones.get(&zone).unwrap();
-                    pace_range
-                }
-                None => pace_map.zones.get(&pace_map.default).unwrap(),
+    }
+}
+
+fn get_power_pace_range(
+    pace_map: &PaceMap,
+    min_watts: f64,
+    max_watts: f64,
+    step: &WorkoutStep,
+) -> Result<PaceRange, EstimateError> {
+    match &pace_map.power {
+        Some(power) => {
+            let power_to_pace = |watts: f64| Pace {
+                time: power.critical_pace.time * power.critical_power as f64 / watts,
+                unit: power.critical_pace.unit,
             };
-            return time_unit_to_miles(pace_range, *value, *unit);
+            Ok(PaceRange {
+                min: power_to_pace(min_watts),
+                max: power_to_pace(max_watts),
+            })
         }
+        None => match pace_map.fallback {
+            ZoneFallback::Error => Err(EstimateError::MissingPowerProfile {
+                step: step.to_string(),
+            }),
+            _ => get_zone_pace_range(pace_map, &pace_map.default, step),
+        },
     }
 }
 
+/// Linear interpolation between the pace map heart rates, clamped at both ends
+fn heart_rate_to_pace(pace_map: &PaceMap, bpm: u32) -> Option<Pace> {
+    let mut points: Vec<(f64, f64)> = pace_map
+        .heart_rates
+        .iter()
+        .map(|hr| {
+            (
+                hr.bpm as f64,
+                hr.pace.time / length_unit_to_miles(hr.pace.unit),
+            )
+        })
+        .collect();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let bpm = bpm as f64;
+    let (first, last) = (points.first()?, points.last()?);
+    let time = if bpm <= first.0 {
+        first.1
+    } else if bpm >= last.0 {
+        last.1
+    } else {
+        let i = points.iter().position(|p| p.0 >= bpm).unwrap();
+        let ((x0, y0), (x1, y1)) = (points[i - 1], points[i]);
+        y0 + (y1 - y0) * (bpm - x0) / (x1 - x0)
+    };
+
+    Some(Pace {
+        time,
+        unit: LengthUnit::Miles,
+    })
+}
+
+const ZONES: [HeartRateZone; 5] = [
+    HeartRateZone::Z1,
+    HeartRateZone::Z2,
+    HeartRateZone::Z3,
+    HeartRateZone::Z4,
+    HeartRateZone::Z5,
+];
+
+pub(crate) const TRAINING_PACES: [TrainingPace; 6] = [
+    TrainingPace::Easy,
+    TrainingPace::Marathon,
+    TrainingPace::HalfMarathon,
+    TrainingPace::Threshold,
+    TrainingPace::Interval,
+    TrainingPace::Repetition,
+];
+
+fn get_zone_pace_range(
+    pace_map: &PaceMap,
+    zone: &HeartRateZone,
+    step: &WorkoutStep,
+) -> Result<PaceRange, EstimateError> {
+    if let Some(pace_range) = pace_map.zones.get(zone) {
+        return Ok(pace_range.clone());
+    }
+
+    let fallback = match pace_map.fallback {
+        ZoneFallback::Error => None,
+        ZoneFallback::Default => pace_map.zones.get(&pace_map.default),
+        ZoneFallback::Nearest => nearest_pace_range(&pace_map.zones, &ZONES, zone),
+    };
+
+    fallback.cloned().ok_or_else(|| EstimateError::MissingZone {
+        zone: zone.clone(),
+        step: step.to_string(),
+    })
+}
+
+fn get_training_pace_range(
+    pace_map: &PaceMap,
+    pace: &TrainingPace,
+    step: &WorkoutStep,
+) -> Result<PaceRange, EstimateError> {
+    if let Some(pace_range) = pace_map.paces.get(pace) {
+        return Ok(pace_range.clone());
+    }
+
+    let fallback = match pace_map.fallback {
+        ZoneFallback::Error => None,
+        ZoneFallback::Default => pace_map.zones.get(&pace_map.default),
+        ZoneFallback::Nearest => nearest_pace_range(&pace_map.paces, &TRAINING_PACES, pace),
+    };
+
+    fallback
+        .cloned()
+        .ok_or_else(|| EstimateError::MissingTrainingPace {
+            pace: pace.clone(),
+            step: step.to_string(),
+        })
+}
+
+/// Closest defined range in `order`, the easier one wins a tie
+fn nearest_pace_range<'a, K: Eq + std::hash::Hash>(
+    ranges: &'a HashMap<K, PaceRange>,
+    order: &[K],
+    key: &K,
+) -> Option<&'a PaceRange> {
+    let index = order.iter().position(|k| k == key).unwrap();
+    (1..order.len())
+        .flat_map(|distance| [index.checked_sub(distance), Some(index + distance)])
+        .flatten()
+        .filter_map(|i| order.get(i))
+        .find_map(|k| ranges.get(k))
+}
+
 #[cfg(test)]
 mod tests;
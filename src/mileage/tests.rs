@@ -74,6 +74,58 @@ lazy_static! {
             ),
         ]),
         default: HeartRateZone::Z1,
+        fallback: ZoneFallback::Error,
+        heart_rates: vec![
+            HeartRatePace {
+                bpm: 180,
+                pace: Pace {
+                    time: 6.0 * 60.0,
+                    unit: LengthUnit::Miles,
+                },
+            },
+            HeartRatePace {
+                bpm: 120,
+                pace: Pace {
+                    time: 12.0 * 60.0,
+                    unit: LengthUnit::Miles,
+                },
+            },
+        ],
+        power: Some(PowerProfile {
+            critical_power: 300,
+            critical_pace: Pace {
+                time: 6.0 * 60.0,
+                unit: LengthUnit::Miles,
+            },
+        }),
+        paces: HashMap::from([
+            (
+                TrainingPace::Marathon,
+                PaceRange {
+                    min: Pace {
+                        time: 7.5 * 60.0,
+                        unit: LengthUnit::Miles,
+                    },
+                    max: Pace {
+                        time: 7.0 * 60.0,
+                        unit: LengthUnit::Miles,
+                    },
+                },
+            ),
+            (
+                TrainingPace::Threshold,
+                PaceRange {
+                    min: Pace {
+                        time: 6.0 * 60.0,
+                        unit: LengthUnit::Miles,
+                    },
+                    max: Pace {
+                        time: 6.0 * 60.0,
+                        unit: LengthUnit::Miles,
+                    },
+                },
+            ),
+        ]),
     };
 }
 
@@ -84,9 +136,32 @@ fn test_empty_workout() {
         intervals: vec![],
         cooldown: None,
     };
-    let mileage = get_mileage(&PACE_MAP, &workout);
+    let mileage = get_mileage(&PACE_MAP, &workout).unwrap();
     assert_eq!(mileage.min, 0.0);
     assert_eq!(mileage.max, 0.0);
+    assert!(!mileage.open);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_open_goal_mileage() {
+    let workout = Workout {
+        warmup: Some(WorkoutStep {
+            goal: Goal::Open,
+            alerts: vec![Alert::HeartRate(HeartRateZone::Z1)],
+        }),
+        intervals: vec![],
+        cooldown: Some(WorkoutStep {
+            goal: Goal::Distance {
+                value: 1.0,
+                unit: LengthUnit::Miles,
+            },
+            alerts: vec![],
+        }),
+    };
+    let mileage = get_mileage(&PACE_MAP, &workout).unwrap();
+    assert_eq!(mileage.min, 1.0);
+    assert_eq!(mileage.max, 1.0);
+    assert!(mileage.open);
 }
 
 #[wasm_bindgen_test(unsupported = test)]
@@ -97,7 +172,7 @@ fn test_workout_with_warmup_and_cooldown() {
                 value: 10.0,
                 unit: TimeUnit::Minutes,
             },
-            alert: Some(Alert::HeartRate(HeartRateZone::Z2)),
+            alerts: vec![Alert::HeartRate(HeartRateZone::Z2)],
         }),
         intervals: vec![],
         cooldown: Some(WorkoutStep {
@@ -105,10 +180,10 @@ fn test_workout_with_warmup_and_cooldown() {
                 value: 5.0,
                 unit: TimeUnit::Minutes,
             },
-            alert: Some(Alert::HeartRate(HeartRateZone::Z1)),
+            alerts: vec![Alert::HeartRate(HeartRateZone::Z1)],
         }),
     };
-    let mileage = get_mileage(&PACE_MAP, &workout);
+    let mileage = get_mileage(&PACE_MAP, &workout).unwrap();
     // Z2 pace: 10-9 min/mile for 10 minutes = 1.0-1.11 miles
     // Z1 pace: 20-10 min/mile for 5 minutes = 0.25-0.5 miles
     println!("mileage: {:?}", mileage);
@@ -123,37 +198,129 @@ fn test_workout_with_intervals() {
         intervals: vec![IntervalBlock {
             repeats: Some(3),
             steps: vec![
-                IntervalStep {
+                IntervalRep::Step(IntervalStep {
                     step: WorkoutStep {
                         goal: Goal::Duration {
                             value: 1.0,
                             unit: TimeUnit::Minutes,
                         },
-                        alert: Some(Alert::HeartRate(HeartRateZone::Z5)),
+                        alerts: vec![Alert::HeartRate(HeartRateZone::Z5)],
                     },
                     has_recovery: false,
-                },
-                IntervalStep {
+                }),
+                IntervalRep::Step(IntervalStep {
                     step: WorkoutStep {
                         goal: Goal::Duration {
                             value: 2.0,
                             unit: TimeUnit::Minutes,
                         },
-                        alert: Some(Alert::HeartRate(HeartRateZone::Z1)),
+                        alerts: vec![Alert::HeartRate(HeartRateZone::Z1)],
                     },
                     has_recovery: true,
-                },
+                }),
             ],
         }],
         cooldown: None,
     };
-    let mileage = get_mileage(&PACE_MAP, &workout);
+    let mileage = get_mileage(&PACE_MAP, &workout).unwrap();
     // Z5 pace: 6-5 min/mile for 1 minute, repeated 3 times = 0.5-0.6 miles
     // Z1 pace: 20-10 min/mile for 2 minutes, repeated 3 times = 0.3-0.6 miles
     assert!(mileage.min >= 0.8 && mileage.min <= 0.9);
     assert!(mileage.max >= 1.2 && mileage.max <= 1.3);
 }
 
+#[wasm_bindgen_test(unsupported = test)]
+fn test_workout_with_nested_intervals() {
+    let workout = Workout {
+        warmup: None,
+        intervals: vec![IntervalBlock {
+            repeats: Some(3),
+            steps: vec![
+                IntervalRep::Block(IntervalBlock {
+                    repeats: Some(4),
+                    steps: vec![
+                        IntervalRep::Step(IntervalStep {
+                            step: WorkoutStep {
+                                goal: Goal::Distance {
+                                    value: 0.25,
+                                    unit: LengthUnit::Miles,
+                                },
+                                alerts: vec![Alert::HeartRate(HeartRateZone::Z5)],
+                            },
+                            has_recovery: false,
+                        }),
+                        IntervalRep::Step(IntervalStep {
+                            step: WorkoutStep {
+                                goal: Goal::Distance {
+                                    value: 0.125,
+                                    unit: LengthUnit::Miles,
+                                },
+                                alerts: vec![],
+                            },
+                            has_recovery: true,
+                        }),
+                    ],
+                }),
+                IntervalRep::Step(IntervalStep {
+                    step: WorkoutStep {
+                        goal: Goal::Duration {
+                            value: 2.0,
+                            unit: TimeUnit::Minutes,
+                        },
+                        alerts: vec![Alert::HeartRate(HeartRateZone::Z1)],
+                    },
+                    has_recovery: true,
+                }),
+            ],
+        }],
+        cooldown: None,
+    };
+    let mileage = get_mileage(&PACE_MAP, &workout).unwrap();
+    // Inner block: 4 x (0.25 + 0.125 miles) = 1.5 miles, repeated 3 times = 4.5 miles
+    // Z1 pace: 20-10 min/mile for 2 minutes, repeated 3 times = 0.3-0.6 miles
+    assert!(mileage.min >= 4.79 && mileage.min <= 4.81);
+    assert!(mileage.max >= 5.09 && mileage.max <= 5.11);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_race_distance_mileage() {
+    let step = WorkoutStep {
+        goal: Goal::Race {
+            distance: RaceDistance::Marathon,
+        },
+        alerts: vec![],
+    };
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert!((mileage.min - 42.195 * 0.621371).abs() < 1e-9);
+    assert_eq!(mileage.min, mileage.max);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_first_of_goal_mileage() {
+    let step = WorkoutStep {
+        goal: Goal::FirstOf {
+            distance: 1.0,
+            length_unit: LengthUnit::Miles,
+            duration: 8.0,
+            time_unit: TimeUnit::Minutes,
+        },
+        alerts: vec![Alert::HeartRate(HeartRateZone::Z2)],
+    };
+    // Z2 is 10-9 min/mile, so 8 minutes end before the mile
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert_eq!(mileage.min, 0.8);
+    assert!((mileage.max - 8.0 / 9.0).abs() < 1e-9);
+
+    // Z5 is 6-5 min/mile, so the mile comes first
+    let step = WorkoutStep {
+        alerts: vec![Alert::HeartRate(HeartRateZone::Z5)],
+        ..step
+    };
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert_eq!(mileage.min, 1.0);
+    assert_eq!(mileage.max, 1.0);
+}
+
 #[wasm_bindgen_test(unsupported = test)]
 fn test_length_unit_conversions() {
     assert_eq!(length_unit_to_miles(LengthUnit::Miles), 1.0);
@@ -197,9 +364,9 @@ fn test_workout_step_mileage() {
             value: 5.0,
             unit: LengthUnit::Miles,
         },
-        alert: None,
+        alerts: vec![],
     };
-    let distance_mileage = get_workout_step_mileage(&PACE_MAP, &distance_step);
+    let distance_mileage = get_workout_step_mileage(&PACE_MAP, &distance_step).unwrap();
     assert_eq!(distance_mileage.min, 5.0);
     assert_eq!(distance_mileage.max, 5.0);
 
@@ -209,12 +376,12 @@ fn test_workout_step_mileage() {
             value: 30.0,
             unit: TimeUnit::Minutes,
         },
-        alert: Some(Alert::PaceThreshold(Pace {
+        alerts: vec![Alert::PaceThreshold(Pace {
             time: 10.0 * 60.0,
             unit: LengthUnit::Miles,
-        })),
+        })],
     };
-    let pace_mileage = get_workout_step_mileage(&PACE_MAP, &pace_step);
+    let pace_mileage = get_workout_step_mileage(&PACE_MAP, &pace_step).unwrap();
     assert_eq!(pace_mileage.min, 3.0);
     assert_eq!(pace_mileage.max, 3.0);
 
@@ -224,9 +391,9 @@ fn test_workout_step_mileage() {
             value: 60.0,
             unit: TimeUnit::Minutes,
         },
-        alert: Some(Alert::HeartRate(HeartRateZone::Z3)),
+        alerts: vec![Alert::HeartRate(HeartRateZone::Z3)],
     };
-    let hr_mileage = get_workout_step_mileage(&PACE_MAP, &hr_step);
+    let hr_mileage = get_workout_step_mileage(&PACE_MAP, &hr_step).unwrap();
     // Z3 pace: 9-7 min/mile for 60 minutes = 6.67-8.57 miles
     assert!(hr_mileage.min >= 6.6 && hr_mileage.min <= 6.7);
     assert!(hr_mileage.max >= 8.5 && hr_mileage.max <= 8.6);
@@ -237,7 +404,7 @@ fn test_workout_step_mileage() {
             value: 30.0,
             unit: TimeUnit::Minutes,
         },
-        alert: Some(Alert::PaceRange {
+        alerts: vec![Alert::PaceRange {
             min: Pace {
                 time: 10.0 * 60.0,
                 unit: LengthUnit::Miles,
@@ -246,23 +413,350 @@ fn test_workout_step_mileage() {
                 time: 8.0 * 60.0,
                 unit: LengthUnit::Miles,
             },
-        }),
+        }],
     };
-    let pace_range_mileage = get_workout_step_mileage(&PACE_MAP, &pace_range_step);
+    let pace_range_mileage = get_workout_step_mileage(&PACE_MAP, &pace_range_step).unwrap();
     // 10-8 min/mile for 30 minutes = 3.0-3.75 miles
     assert_eq!(pace_range_mileage.min, 3.0);
     assert_eq!(pace_range_mileage.max, 3.75);
 
+    // The same range written fast-to-slow
+    let reversed_step = WorkoutStep {
+        goal: pace_range_step.goal.clone(),
+        alerts: vec![Alert::PaceRange {
+            min: Pace {
+                time: 8.0 * 60.0,
+                unit: LengthUnit::Miles,
+            },
+            max: Pace {
+                time: 10.0 * 60.0,
+                unit: LengthUnit::Miles,
+            },
+        }],
+    };
+    let reversed_mileage = get_workout_step_mileage(&PACE_MAP, &reversed_step).unwrap();
+    assert_eq!(reversed_mileage.min, 3.0);
+    assert_eq!(reversed_mileage.max, 3.75);
+
     // Test duration-based step with no alert (using default pace zone)
     let default_step = WorkoutStep {
         goal: Goal::Duration {
             value: 20.0,
             unit: TimeUnit::Minutes,
         },
-        alert: None,
+        alerts: vec![],
     };
-    let default_mileage = get_workout_step_mileage(&PACE_MAP, &default_step);
+    let default_mileage = get_workout_step_mileage(&PACE_MAP, &default_step).unwrap();
     // Default is Z1: 20-10 min/mile for 20 minutes = 1.0-2.0 miles
     assert_eq!(default_mileage.min, 1.0);
     assert_eq!(default_mileage.max, 2.0);
 }
+
+fn sparse_pace_map(fallback: ZoneFallback) -> PaceMap {
+    PaceMap {
+        zones: HashMap::from([
+            (
+                HeartRateZone::Z2,
+                PACE_MAP.zones[&HeartRateZone::Z2].clone(),
+            ),
+            (
+                HeartRateZone::Z5,
+                PACE_MAP.zones[&HeartRateZone::Z5].clone(),
+            ),
+        ]),
+        default: HeartRateZone::Z5,
+        fallback,
+        heart_rates: vec![],
+        power: None,
+        paces: HashMap::new(),
+    }
+}
+
+fn zone_step(zone: HeartRateZone) -> WorkoutStep {
+    WorkoutStep {
+        goal: Goal::Duration {
+            value: 60.0,
+            unit: TimeUnit::Minutes,
+        },
+        alerts: vec![Alert::HeartRate(zone)],
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_zone_range_mileage() {
+    // Slow end of Z1 and fast end of Z2: 20-9 min/mile for 60 minutes
+    let step = heart_rate_step(Alert::HeartRateZoneRange {
+        min: HeartRateZone::Z1,
+        max: HeartRateZone::Z2,
+    });
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert_eq!(mileage.min, 3.0);
+    assert!((mileage.max - 60.0 / 9.0).abs() < 1e-9);
+
+    // Z4 falls back to Z5 like a single zone would
+    let step = heart_rate_step(Alert::HeartRateZoneRange {
+        min: HeartRateZone::Z2,
+        max: HeartRateZone::Z4,
+    });
+    let mileage = get_workout_step_mileage(&sparse_pace_map(ZoneFallback::Nearest), &step).unwrap();
+    assert_eq!(mileage.min, 6.0);
+    assert_eq!(mileage.max, 12.0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_missing_zone_error() {
+    let pace_map = sparse_pace_map(ZoneFallback::Error);
+    let error = get_workout_step_mileage(&pace_map, &zone_step(HeartRateZone::Z3)).unwrap_err();
+    assert_eq!(
+        error,
+        crate::error::EstimateError::MissingZone {
+            zone: HeartRateZone::Z3,
+            step: "60 min @z3".to_string(),
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "Pace map has no range for z3, needed by `60 min @z3`"
+    );
+
+    // Distance goals do not need a pace, so they never fail
+    let distance_step = WorkoutStep {
+        goal: Goal::Distance {
+            value: 1.0,
+            unit: LengthUnit::Miles,
+        },
+        alerts: vec![Alert::HeartRate(HeartRateZone::Z3)],
+    };
+    assert!(get_workout_step_mileage(&pace_map, &distance_step).is_ok());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_missing_zone_default_fallback() {
+    let pace_map = sparse_pace_map(ZoneFallback::Default);
+    let mileage = get_workout_step_mileage(&pace_map, &zone_step(HeartRateZone::Z3)).unwrap();
+    // Default is Z5: 6-5 min/mile for 60 minutes = 10-12 miles
+    assert_eq!(mileage.min, 10.0);
+    assert_eq!(mileage.max, 12.0);
+
+    // A missing default zone still fails
+    let mut pace_map = pace_map;
+    pace_map.default = HeartRateZone::Z1;
+    assert!(get_workout_step_mileage(&pace_map, &zone_step(HeartRateZone::Z3)).is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_missing_zone_nearest_fallback() {
+    let pace_map = sparse_pace_map(ZoneFallback::Nearest);
+
+    // Z1 is closest to Z2
+    let mileage = get_workout_step_mileage(&pace_map, &zone_step(HeartRateZone::Z1)).unwrap();
+    assert_eq!(mileage.min, 6.0);
+
+    // Z4 is closest to Z5
+    let mileage = get_workout_step_mileage(&pace_map, &zone_step(HeartRateZone::Z4)).unwrap();
+    assert_eq!(mileage.min, 10.0);
+
+    // Z3 is closer to Z2 than to Z5
+    let mileage = get_workout_step_mileage(&pace_map, &zone_step(HeartRateZone::Z3)).unwrap();
+    assert_eq!(mileage.min, 6.0);
+
+    // Z3 is as close to Z2 as to Z4, the easier zone wins
+    let mut pace_map = pace_map;
+    pace_map.zones.remove(&HeartRateZone::Z5);
+    pace_map.zones.insert(
+        HeartRateZone::Z4,
+        PACE_MAP.zones[&HeartRateZone::Z4].clone(),
+    );
+    let mileage = get_workout_step_mileage(&pace_map, &zone_step(HeartRateZone::Z3)).unwrap();
+    assert_eq!(mileage.min, 6.0);
+    let mileage = get_workout_step_mileage(&pace_map, &zone_step(HeartRateZone::Z4)).unwrap();
+    assert_ne!(mileage.min, 6.0);
+}
+
+fn heart_rate_step(alert: Alert) -> WorkoutStep {
+    WorkoutStep {
+        goal: Goal::Duration {
+            value: 60.0,
+            unit: TimeUnit::Minutes,
+        },
+        alerts: vec![alert],
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_heart_rate_threshold_mileage() {
+    // 150bpm is halfway between 12:00/mile at 120bpm and 6:00/mile at 180bpm
+    let step = heart_rate_step(Alert::HeartRateThreshold {
+        bpm: 150,
+        ceiling: false,
+    });
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert!((mileage.min - 60.0 / 9.0).abs() < 1e-9);
+    assert!((mileage.max - 60.0 / 9.0).abs() < 1e-9);
+
+    // Heart rates outside of the relation are clamped
+    let step = heart_rate_step(Alert::HeartRateThreshold {
+        bpm: 200,
+        ceiling: false,
+    });
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert_eq!(mileage.min, 10.0);
+
+    // A ceiling spans from the slowest known heart rate
+    let step = heart_rate_step(Alert::HeartRateThreshold {
+        bpm: 150,
+        ceiling: true,
+    });
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert_eq!(mileage.min, 5.0);
+    assert!((mileage.max - 60.0 / 9.0).abs() < 1e-9);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_heart_rate_range_mileage() {
+    let step = heart_rate_step(Alert::HeartRateRange { min: 140, max: 160 });
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    // 140bpm = 10:00/mile, 160bpm = 8:00/mile
+    assert!((mileage.min - 6.0).abs() < 1e-9);
+    assert!((mileage.max - 7.5).abs() < 1e-9);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_heart_rate_without_relation() {
+    let step = heart_rate_step(Alert::HeartRateRange { min: 140, max: 160 });
+
+    let pace_map = sparse_pace_map(ZoneFallback::Error);
+    let error = get_workout_step_mileage(&pace_map, &step).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Pace map has no heart rates, needed by `60 min @140-160bpm`"
+    );
+
+    // Falls back to the default zone, Z5: 6-5 min/mile for 60 minutes
+    let pace_map = sparse_pace_map(ZoneFallback::Default);
+    let mileage = get_workout_step_mileage(&pace_map, &step).unwrap();
+    assert_eq!(mileage.min, 10.0);
+    assert_eq!(mileage.max, 12.0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_power_mileage() {
+    // Critical power is 300W at 6:00/mile, so 250W is 7:12/mile
+    let step = heart_rate_step(Alert::PowerThreshold { watts: 250 });
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert!((mileage.min - 60.0 / 7.2).abs() < 1e-9);
+    assert!((mileage.max - 60.0 / 7.2).abs() < 1e-9);
+
+    let step = heart_rate_step(Alert::PowerRange { min: 240, max: 270 });
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert!((mileage.min - 8.0).abs() < 1e-9);
+    assert!((mileage.max - 9.0).abs() < 1e-9);
+
+    let step = heart_rate_step(Alert::CriticalPower { percent: 110.0 });
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert!((mileage.min - 11.0).abs() < 1e-9);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_power_without_profile() {
+    let step = heart_rate_step(Alert::CriticalPower { percent: 105.0 });
+
+    let pace_map = sparse_pace_map(ZoneFallback::Error);
+    let error = get_workout_step_mileage(&pace_map, &step).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Pace map has no power profile, needed by `60 min @105%CP`"
+    );
+
+    let pace_map = sparse_pace_map(ZoneFallback::Nearest);
+    let mileage = get_workout_step_mileage(&pace_map, &step).unwrap();
+    assert_eq!(mileage.min, 10.0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_speed_mileage() {
+    // Speed is the distance covered in an hour
+    let step = heart_rate_step(Alert::SpeedThreshold(Speed {
+        value: 12.0,
+        unit: SpeedUnit::KilometersPerHour,
+    }));
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert!((mileage.min - 12.0 * 0.621371).abs() < 1e-9);
+    assert!((mileage.max - 12.0 * 0.621371).abs() < 1e-9);
+
+    let step = heart_rate_step(Alert::SpeedRange {
+        min: Speed {
+            value: 7.5,
+            unit: SpeedUnit::MilesPerHour,
+        },
+        max: Speed {
+            value: 8.0,
+            unit: SpeedUnit::MilesPerHour,
+        },
+    });
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert!((mileage.min - 7.5).abs() < 1e-9);
+    assert!((mileage.max - 8.0).abs() < 1e-9);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_training_pace_mileage() {
+    // MP is 7:30-7:00/mile for 60 minutes
+    let step = heart_rate_step(Alert::TrainingPace(TrainingPace::Marathon));
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert_eq!(mileage.min, 8.0);
+    assert!((mileage.max - 60.0 / 7.0).abs() < 1e-9);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_missing_training_pace() {
+    let step = heart_rate_step(Alert::TrainingPace(TrainingPace::Interval));
+
+    let mut pace_map = PACE_MAP.clone();
+    let error = get_workout_step_mileage(&pace_map, &step).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Pace map has no range for I, needed by `60 min @I`"
+    );
+
+    // T and R are as close to I, only T is defined: 6:00/mile
+    pace_map.fallback = ZoneFallback::Nearest;
+    let mileage = get_workout_step_mileage(&pace_map, &step).unwrap();
+    assert_eq!(mileage.min, 10.0);
+
+    // Default is Z1: 20-10 min/mile
+    pace_map.fallback = ZoneFallback::Default;
+    let mileage = get_workout_step_mileage(&pace_map, &step).unwrap();
+    assert_eq!(mileage.min, 3.0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_cadence_mileage() {
+    // Cadence runs in the default zone, Z1: 20-10 min/mile for 60 minutes
+    let step = heart_rate_step(Alert::CadenceRange { min: 175, max: 185 });
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert_eq!(mileage.min, 3.0);
+    assert_eq!(mileage.max, 6.0);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_multiple_alerts_mileage() {
+    let mut step = heart_rate_step(Alert::CadenceThreshold { spm: 180 });
+    step.alerts.push(Alert::HeartRate(HeartRateZone::Z5));
+    // Heart rate drives the estimate over cadence, Z5: 6-5 min/mile for 60 minutes
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert_eq!(mileage.min, 10.0);
+
+    step.alerts.push(Alert::PowerThreshold { watts: 250 });
+    // Power drives the estimate over heart rate, 7:12/mile
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert!((mileage.min - 60.0 / 7.2).abs() < 1e-9);
+
+    step.alerts.push(Alert::PaceThreshold(Pace {
+        time: 7.5 * 60.0,
+        unit: LengthUnit::Miles,
+    }));
+    // Pace drives the estimate over everything else
+    let mileage = get_workout_step_mileage(&PACE_MAP, &step).unwrap();
+    assert_eq!(mileage.min, 8.0);
+}
@@ -11,7 +11,9 @@ use wasm_bindgen::prelude::*;
 #[cfg(feature = "ios")]
 use uniffi;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+use crate::error::Diagnostic;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ios", derive(uniffi::Record))]
 #[cfg_attr(feature = "wasm", derive(Tsify))]
 #[cfg_attr(feature = "wasm", tsify(into_wasm_abi))]
@@ -22,15 +24,24 @@ pub struct Workout {
     pub cooldown: Option<WorkoutStep>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ios", derive(uniffi::Record))]
 #[cfg_attr(feature = "wasm", derive(Tsify))]
 pub struct IntervalBlock {
     pub repeats: Option<u32>,
-    pub steps: Vec<IntervalStep>,
+    pub steps: Vec<IntervalRep>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum IntervalRep {
+    Step(IntervalStep),
+    Block(IntervalBlock),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ios", derive(uniffi::Record))]
 #[cfg_attr(feature = "wasm", derive(Tsify))]
 pub struct IntervalStep {
@@ -38,31 +49,111 @@ pub struct IntervalStep {
     pub has_recovery: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ios", derive(uniffi::Record))]
 #[cfg_attr(feature = "wasm", derive(Tsify))]
 pub struct WorkoutStep {
     pub goal: Goal,
-    pub alert: Option<Alert>,
+    pub alerts: Vec<Alert>, // At most one alert of each kind
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ios", derive(uniffi::Enum))]
 #[cfg_attr(feature = "wasm", derive(Tsify))]
+#[cfg_attr(feature = "wasm", tsify(from_wasm_abi))]
 #[serde(tag = "type", rename_all = "camelCase")]
+#[rustfmt::skip]
 pub enum Goal {
     Distance { value: f64, unit: LengthUnit },
     Duration { value: f64, unit: TimeUnit },
+    // `10k` or `hm race`, printed back by race name rather than as `10 km`
+    Race { distance: RaceDistance },
+    // `open` or `lap`, printed back as `open`, ends when the lap button is pressed
+    Open,
+    // `1 mile or 8 min`, ends on whichever limit is reached first
+    FirstOf { distance: f64, length_unit: LengthUnit, duration: f64, time_unit: TimeUnit },
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "camelCase")]
+pub enum RaceDistance {
+    Mile,
+    FiveK,
+    TenK,
+    HalfMarathon,
+    Marathon,
+}
+
+impl RaceDistance {
+    /// Exact length of the race
+    pub fn length(&self) -> (f64, LengthUnit) {
+        match self {
+            RaceDistance::Mile => (1.0, LengthUnit::Miles),
+            RaceDistance::FiveK => (5.0, LengthUnit::Kilometers),
+            RaceDistance::TenK => (10.0, LengthUnit::Kilometers),
+            RaceDistance::HalfMarathon => (21.0975, LengthUnit::Kilometers),
+            RaceDistance::Marathon => (42.195, LengthUnit::Kilometers),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ios", derive(uniffi::Enum))]
 #[cfg_attr(feature = "wasm", derive(Tsify))]
+#[cfg_attr(feature = "wasm", tsify(from_wasm_abi))]
 #[serde(tag = "type", rename_all = "camelCase")]
+#[rustfmt::skip]
 pub enum Alert {
     HeartRate(HeartRateZone),
+    // `z1-z2` or `z1-2`
+    HeartRateZoneRange { min: HeartRateZone, max: HeartRateZone },
+    // `150bpm`, or `<150bpm` to stay below it
+    HeartRateThreshold { bpm: u32, ceiling: bool },
+    HeartRateRange { min: u32, max: u32 },
+    PowerThreshold { watts: u32 },
+    PowerRange { min: u32, max: u32 },
+    // Percentage of the athlete's critical power
+    CriticalPower { percent: f64 },
+    CadenceThreshold { spm: u32 },
+    CadenceRange { min: u32, max: u32 },
+    SpeedThreshold(Speed),
+    SpeedRange { min: Speed, max: Speed },
     PaceThreshold(Pace),
     PaceRange { min: Pace, max: Pace },
+    TrainingPace(TrainingPace),
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ios", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "camelCase")]
+pub enum AlertKind {
+    HeartRate,
+    Power,
+    Cadence,
+    Pace,
+}
+
+impl Alert {
+    pub fn kind(&self) -> AlertKind {
+        match self {
+            Alert::HeartRate(_)
+            | Alert::HeartRateZoneRange { .. }
+            | Alert::HeartRateThreshold { .. }
+            | Alert::HeartRateRange { .. } => AlertKind::HeartRate,
+            Alert::PowerThreshold { .. }
+            | Alert::PowerRange { .. }
+            | Alert::CriticalPower { .. } => AlertKind::Power,
+            Alert::CadenceThreshold { .. } | Alert::CadenceRange { .. } => AlertKind::Cadence,
+            Alert::SpeedThreshold(_)
+            | Alert::SpeedRange { .. }
+            | Alert::PaceThreshold(_)
+            | Alert::PaceRange { .. }
+            | Alert::TrainingPace(_) => AlertKind::Pace,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
@@ -77,7 +168,21 @@ pub enum HeartRateZone {
     Z5,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Named paces of Jack Daniels' training plans, easiest first
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ios", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "camelCase")]
+pub enum TrainingPace {
+    Easy,         // `E`
+    Marathon,     // `MP`
+    HalfMarathon, // `HMP`
+    Threshold,    // `T`
+    Interval,     // `I`
+    Repetition,   // `R`
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ios", derive(uniffi::Record))]
 #[cfg_attr(feature = "wasm", derive(Tsify))]
 pub struct Pace {
@@ -97,6 +202,23 @@ pub enum LengthUnit {
     Kilometers,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct Speed {
+    pub value: f64,
+    pub unit: SpeedUnit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "camelCase")]
+pub enum SpeedUnit {
+    KilometersPerHour,
+    MilesPerHour,
+}
+
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
 #[cfg_attr(feature = "ios", derive(uniffi::Enum))]
 #[cfg_attr(feature = "wasm", derive(Tsify))]
@@ -107,7 +229,7 @@ pub enum TimeUnit {
     Hours,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "ios", derive(uniffi::Record))]
 #[cfg_attr(feature = "wasm", derive(Tsify))]
 pub struct PaceRange {
@@ -118,12 +240,57 @@ pub struct PaceRange {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[cfg_attr(feature = "ios", derive(uniffi::Record))]
 #[cfg_attr(feature = "wasm", derive(Tsify))]
+#[cfg_attr(feature = "wasm", tsify(into_wasm_abi))]
 #[cfg_attr(feature = "wasm", tsify(from_wasm_abi))]
 pub struct PaceMap {
     pub zones: HashMap<HeartRateZone, PaceRange>,
     pub default: HeartRateZone,
+    #[serde(default)]
+    pub fallback: ZoneFallback,
+    #[serde(default)]
+    pub heart_rates: Vec<HeartRatePace>, // Interpolated to convert bpm alerts into paces
+    #[serde(default)]
+    pub power: Option<PowerProfile>,
+    #[serde(default)]
+    pub paces: HashMap<TrainingPace, PaceRange>, // Resolves `@MP`, `@T`, ... like zones
 }
 
+/// Running power is taken as proportional to speed, anchored at critical power
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct PowerProfile {
+    pub critical_power: u32, // Watts
+    pub critical_pace: Pace, // Pace held at critical power
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct HeartRatePace {
+    pub bpm: u32,
+    pub pace: Pace,
+}
+
+/// What to do when a step needs a zone that is missing from the pace map
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "ios", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "camelCase")]
+pub enum ZoneFallback {
+    /// Fail the estimate with `EstimateError::MissingZone`
+    #[default]
+    Error,
+    /// Use the pace map's default zone, also used for bpm and power alerts the map cannot convert
+    Default,
+    /// Use the closest defined zone, preferring the easier one on a tie
+    Nearest,
+}
+
+/// Estimated distance range in miles
+///
+/// `open` is set when some steps end on the lap button, they count as zero here and in
+/// `WorkoutDuration`
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[cfg_attr(feature = "ios", derive(uniffi::Record))]
 #[cfg_attr(feature = "wasm", derive(Tsify))]
@@ -131,4 +298,165 @@ pub struct PaceMap {
 pub struct Mileage {
     pub min: f64,
     pub max: f64,
+    pub open: bool,
+}
+
+/// Estimated time range in seconds, `open` as in `Mileage`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[cfg_attr(feature = "wasm", tsify(into_wasm_abi))]
+pub struct WorkoutDuration {
+    pub min: f64,
+    pub max: f64,
+    pub open: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ios", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "camelCase")]
+pub enum Phase {
+    Warmup,
+    Interval,
+    Recovery,
+    Cooldown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct Estimate {
+    pub mileage: Mileage,
+    pub duration: WorkoutDuration,
+}
+
+/// A step of `Workout::expand`, with repeats unrolled
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct ExpandedStep {
+    pub index: u32, // Position in the expanded workout, starting at 0
+    pub step: WorkoutStep,
+    pub phase: Phase,
+    pub block: Option<u32>, // Index of the interval block, none for warmup and cooldown
+    pub rep: Option<u32>,   // Repetition of the innermost repeat, starting at 1
+    pub repeats: Option<u32>, // Repetitions of the innermost repeat
+    pub sets: Vec<Repetition>, // Repetition of every repeat around the step, outermost first
+    pub recovery: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct Repetition {
+    pub rep: u32, // Starting at 1
+    pub repeats: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct StepEstimate {
+    pub step: WorkoutStep,
+    pub phase: Phase,
+    pub block: Option<u32>, // Index of the interval block, none for warmup and cooldown
+    pub rep: Option<u32>,   // Repetition of the innermost repeat, starting at 1
+    pub zone: Option<HeartRateZone>,
+    pub estimate: Estimate,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[cfg_attr(feature = "wasm", tsify(into_wasm_abi))]
+pub struct Breakdown {
+    pub steps: Vec<StepEstimate>,
+    pub phases: HashMap<Phase, Estimate>,
+    pub zones: HashMap<HeartRateZone, Estimate>,
+}
+
+/// A workout read from a FIT file, with what could not be carried over
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[cfg_attr(feature = "wasm", tsify(into_wasm_abi))]
+pub struct FitWorkout {
+    pub name: Option<String>,
+    pub workout: Workout,
+    pub warnings: Vec<Diagnostic>, // Dropped targets and durations, then `validate` warnings
+}
+
+/// How forgiving an `AlertEvaluator` is, all shares are of the target value
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[cfg_attr(feature = "wasm", tsify(from_wasm_abi))]
+pub struct EvaluatorConfig {
+    pub tolerance: f64,  // Widens the target on both sides, 0.02 for 2%
+    pub smoothing: u32,  // Number of readings averaged, 1 to use each reading as is
+    pub hysteresis: f64, // How far back inside the target a reading must be to count as in range
+}
+
+impl Default for EvaluatorConfig {
+    fn default() -> Self {
+        EvaluatorConfig {
+            tolerance: 0.02,
+            smoothing: 5,
+            hysteresis: 0.01,
+        }
+    }
+}
+
+/// A reading from the watch, `elapsed` and `distance` count from the start of the session
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[cfg_attr(feature = "wasm", tsify(from_wasm_abi))]
+pub struct Sample {
+    pub elapsed: f64,  // Seconds
+    pub distance: f64, // Meters
+    pub heart_rate: Option<u32>,
+    pub pace: Option<Pace>,
+    pub power: Option<u32>,
+    pub cadence: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "camelCase")]
+pub enum SessionState {
+    Ready,
+    Running,
+    Paused,
+    Finished,
+}
+
+/// Where a reading falls against an alert, below the target is `TooSlow` for every metric
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "camelCase")]
+pub enum AlertStatus {
+    TooSlow,
+    InRange,
+    TooFast,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "ios", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[rustfmt::skip]
+pub enum SessionEvent {
+    StepStarted { index: u32 },
+    // Active seconds and meters spent in the step, pauses excluded
+    StepCompleted { index: u32, elapsed: f64, distance: f64 },
+    StepSkipped { index: u32 },
+    // Sent when the status of an alert of the current step changes
+    Alert { index: u32, alert: Alert, status: AlertStatus },
+    Paused,
+    Resumed,
+    Finished,
 }
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use crate::error::PaceMapError;
+use crate::mileage::length_unit_to_miles;
+use crate::models::*;
+
+// Share of VDOT run in each zone, slow end first
+const ZONE_INTENSITIES: [(HeartRateZone, f64, f64); 5] = [
+    (HeartRateZone::Z1, 0.55, 0.65),
+    (HeartRateZone::Z2, 0.65, 0.75),
+    (HeartRateZone::Z3, 0.75, 0.84),
+    (HeartRateZone::Z4, 0.84, 0.92),
+    (HeartRateZone::Z5, 0.92, 1.0),
+];
+
+// Marathon and half marathon paces are race predictions instead
+const TRAINING_PACE_INTENSITIES: [(TrainingPace, f64, f64); 4] = [
+    (TrainingPace::Easy, 0.59, 0.74),
+    (TrainingPace::Threshold, 0.83, 0.88),
+    (TrainingPace::Interval, 0.95, 1.0),
+    (TrainingPace::Repetition, 1.05, 1.1),
+];
+
+const MARATHON_METERS: f64 = 42195.0;
+const HALF_MARATHON_METERS: f64 = 21097.5;
+
+/// Pace map of a runner who covered `race` in `time` seconds, after Daniels and Gilbert's VDOT
+pub fn get_pace_map(race: &Goal, time: f64) -> Result<PaceMap, PaceMapError> {
+    let (value, unit) = match race {
+        Goal::Distance { value, unit } => (*value, *unit),
+        Goal::Race { distance } => distance.length(),
+        Goal::Duration { .. } | Goal::FirstOf { .. } | Goal::Open => {
+            return Err(PaceMapError::NotADistance {
+                goal: race.to_string(),
+            })
+        }
+    };
+    let invalid = || PaceMapError::InvalidResult {
+        race: race.to_string(),
+        time,
+    };
+
+    let meters = value * length_unit_to_miles(unit) / length_unit_to_miles(LengthUnit::Meters);
+    if meters <= 0.0 || time <= 0.0 {
+        return Err(invalid());
+    }
+    let vdot = get_vdot(meters, time);
+    if !vdot.is_finite() || vdot <= 0.0 {
+        return Err(invalid());
+    }
+
+    let intensity_range = |slow: f64, fast: f64| PaceRange {
+        min: velocity_to_pace(oxygen_velocity(vdot * slow)),
+        max: velocity_to_pace(oxygen_velocity(vdot * fast)),
+    };
+    let race_range = |meters: f64| {
+        let pace = velocity_to_pace(meters / (get_race_time(vdot, meters) / 60.0));
+        PaceRange {
+            min: pace.clone(),
+            max: pace,
+        }
+    };
+
+    let zones = ZONE_INTENSITIES
+        .iter()
+        .map(|(zone, slow, fast)| (zone.clone(), intensity_range(*slow, *fast)))
+        .collect();
+    let mut paces: HashMap<TrainingPace, PaceRange> = TRAINING_PACE_INTENSITIES
+        .iter()
+        .map(|(pace, slow, fast)| (pace.clone(), intensity_range(*slow, *fast)))
+        .collect();
+    paces.insert(TrainingPace::Marathon, race_range(MARATHON_METERS));
+    paces.insert(TrainingPace::HalfMarathon, race_range(HALF_MARATHON_METERS));
+
+    Ok(PaceMap {
+        zones,
+        default: HeartRateZone::Z2,
+        fallback: ZoneFallback::default(),
+        heart_rates: Vec::new(),
+        power: None,
+        paces,
+    })
+}
+
+/// Oxygen uptake, in ml/kg/min, of running at `velocity` meters per minute
+fn oxygen_cost(velocity: f64) -> f64 {
+    -4.60 + 0.182258 * velocity + 0.000104 * velocity * velocity
+}
+
+/// Inverse of `oxygen_cost`
+fn oxygen_velocity(vo2: f64) -> f64 {
+    let (a, b, c) = (0.000104, 0.182258, -4.60 - vo2);
+    (-b + (b * b - 4.0 * a * c).sqrt()) / (2.0 * a)
+}
+
+/// Share of VO2max that can be held for `minutes`
+fn sustainable_fraction(minutes: f64) -> f64 {
+    0.8 + 0.1894393 * (-0.012778 * minutes).exp() + 0.2989558 * (-0.1932605 * minutes).exp()
+}
+
+fn get_vdot(meters: f64, seconds: f64) -> f64 {
+    let minutes = seconds / 60.0;
+    oxygen_cost(meters / minutes) / sustainable_fraction(minutes)
+}
+
+/// Seconds to race `meters` at `vdot`, bisecting since a slower time always gives a lower VDOT
+fn get_race_time(vdot: f64, meters: f64) -> f64 {
+    let (mut fast, mut slow) = (1.0, 24.0 * 3600.0);
+    for _ in 0..100 {
+        let time = (fast + slow) / 2.0;
+        if get_vdot(meters, time) > vdot {
+            fast = time;
+        } else {
+            slow = time;
+        }
+    }
+    (fast + slow) / 2.0
+}
+
+fn velocity_to_pace(velocity: f64) -> Pace {
+    Pace {
+        time: 60_000.0 / velocity,
+        unit: LengthUnit::Kilometers,
+    }
+}
+
+#[cfg(test)]
+mod tests;
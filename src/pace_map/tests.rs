@@ -0,0 +1,91 @@
+use super::*;
+use crate::mileage::get_mileage;
+use crate::parser::parse_workout;
+use wasm_bindgen_test::*;
+
+fn seconds_per_km(pace: &Pace) -> f64 {
+    pace.time / length_unit_to_miles(pace.unit) * length_unit_to_miles(LengthUnit::Kilometers)
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_vdot() {
+    // Daniels' tables: 19:57 for 5k and 3:10:49 for the marathon are both VDOT 50
+    assert!((get_vdot(5000.0, 19.0 * 60.0 + 57.0) - 50.0).abs() < 0.1);
+    assert!((get_vdot(42195.0, 3.0 * 3600.0 + 10.0 * 60.0 + 49.0) - 50.0).abs() < 0.1);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_pace_map_from_race() {
+    let race = Goal::Race {
+        distance: RaceDistance::FiveK,
+    };
+    let pace_map = get_pace_map(&race, 19.0 * 60.0 + 57.0).unwrap();
+
+    assert_eq!(pace_map.zones.len(), 5);
+    assert_eq!(pace_map.paces.len(), 6);
+
+    // Marathon pace is the predicted 3:10:49, about 4:31/km
+    let marathon = &pace_map.paces[&TrainingPace::Marathon];
+    assert!((seconds_per_km(&marathon.min) - 271.5).abs() < 1.0);
+
+    // Threshold pace is about 4:15/km, interval pace about 3:55/km
+    let threshold = &pace_map.paces[&TrainingPace::Threshold];
+    assert!((seconds_per_km(&threshold.max) - 255.0).abs() < 5.0);
+    let interval = &pace_map.paces[&TrainingPace::Interval];
+    assert!((seconds_per_km(&interval.max) - 235.0).abs() < 5.0);
+
+    // Zones follow each other, each one slow end first
+    let zones: Vec<&PaceRange> = ZONE_INTENSITIES
+        .iter()
+        .map(|(zone, _, _)| &pace_map.zones[zone])
+        .collect();
+    for zone in &zones {
+        assert!(zone.min.time > zone.max.time);
+    }
+    for pair in zones.windows(2) {
+        assert!((pair[0].max.time - pair[1].min.time).abs() < 1e-9);
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_pace_map_estimates_workouts() {
+    let race = Goal::Distance {
+        value: 10.0,
+        unit: LengthUnit::Kilometers,
+    };
+    let pace_map = get_pace_map(&race, 45.0 * 60.0).unwrap();
+
+    let workout =
+        parse_workout("20 min @E + 4 x (5 min @T @z4 + 1 min recovery) + 30 min @MP").unwrap();
+    let mileage = get_mileage(&pace_map, &workout).unwrap();
+    assert!(mileage.min > 0.0 && mileage.min < mileage.max);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_pace_map_invalid_race() {
+    let duration = Goal::Duration {
+        value: 30.0,
+        unit: TimeUnit::Minutes,
+    };
+    let error = get_pace_map(&duration, 1800.0).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Cannot derive paces from `30 min`, it is not a race distance"
+    );
+
+    let mile = Goal::Race {
+        distance: RaceDistance::Mile,
+    };
+    assert_eq!(
+        get_pace_map(&mile, 0.0).unwrap_err(),
+        PaceMapError::InvalidResult {
+            race: "mile".to_string(),
+            time: 0.0
+        }
+    );
+    // Slower than walking
+    assert!(matches!(
+        get_pace_map(&mile, 5.0 * 3600.0),
+        Err(PaceMapError::InvalidResult { .. })
+    ));
+}
@@ -1,15 +1,39 @@
 use pest::Parser;
 use pest_derive::Parser;
 
+use crate::error::{DashError, Diagnostic, ErrorCode, Severity, Span};
 use crate::models::*;
+use crate::validate::{validate_source, SourceMap, StepSpans};
 
 #[derive(Parser)]
 #[grammar = "dash.pest"]
 pub struct DashParser;
 
-pub fn parse_workout(input: &str) -> Result<Workout, pest::error::Error<Rule>> {
-    let pairs = DashParser::parse(Rule::workout, input)?;
+pub fn parse_workout(input: &str) -> Result<Workout, DashError> {
+    let (workout, source) = parse_workout_source(input)?;
 
+    let diagnostics = validate_source(&workout, &source);
+    if let Some(error) = diagnostics
+        .into_iter()
+        .find(|d| d.severity == Severity::Error)
+    {
+        return Err(DashError::from_diagnostic(error, input));
+    }
+
+    Ok(workout)
+}
+
+/// Parses the workout and returns every validation error and warning with its span
+pub fn lint_workout(input: &str) -> Result<Vec<Diagnostic>, DashError> {
+    let (workout, source) = parse_workout_source(input)?;
+    Ok(validate_source(&workout, &source))
+}
+
+fn parse_workout_source(input: &str) -> Result<(Workout, SourceMap), DashError> {
+    let pairs =
+        DashParser::parse(Rule::workout, input).map_err(|e| DashError::from_pest(e, input))?;
+
+    let mut source = SourceMap::default();
     let mut warmup = None;
     let mut intervals = Vec::new();
     let mut cooldown = None;
@@ -18,78 +42,212 @@ pub fn parse_workout(input: &str) -> Result<Workout, pest::error::Error<Rule>> {
     for pair in pairs.into_iter().next().unwrap().into_inner() {
         match pair.as_rule() {
             Rule::warmup_step => {
-                warmup = Some(parse_workout_step(pair.into_inner().next().unwrap())?);
+                warmup = Some(parse_workout_step(
+                    pair.into_inner().next().unwrap(),
+                    &mut source,
+                )?);
             }
             Rule::cooldown_step => {
-                cooldown = Some(parse_workout_step(pair.into_inner().next().unwrap())?);
+                cooldown = Some(parse_workout_step(
+                    pair.into_inner().next().unwrap(),
+                    &mut source,
+                )?);
             }
             Rule::interval_blocks => {
                 let inner_pairs: Vec<_> = pair.into_inner().collect();
-                intervals = parse_interval_blocks(inner_pairs)?;
+                intervals = parse_interval_blocks(inner_pairs, &mut source)?;
             }
             Rule::EOI => {}
             _ => unreachable!(),
         }
     }
 
-    Ok(Workout {
-        warmup,
-        intervals,
-        cooldown,
-    })
+    Ok((
+        Workout {
+            warmup,
+            intervals,
+            cooldown,
+        },
+        source,
+    ))
+}
+
+fn span_of(pair: &pest::iterators::Pair<Rule>) -> Span {
+    Span {
+        start: pair.as_span().start() as u32,
+        end: pair.as_span().end() as u32,
+    }
 }
 
 fn parse_workout_step(
     pair: pest::iterators::Pair<Rule>,
-) -> Result<WorkoutStep, pest::error::Error<Rule>> {
+    source: &mut SourceMap,
+) -> Result<WorkoutStep, DashError> {
     let mut goal = None;
-    let mut alert = None;
+    let mut alerts = Vec::new();
+    let mut spans = StepSpans {
+        goal: span_of(&pair),
+        alerts: Vec::new(),
+    };
 
     for part in pair.into_inner() {
         match part.as_rule() {
             Rule::distance_goal => {
-                let mut inner = part.into_inner();
-                let value = inner.next().unwrap().as_str().parse::<f64>().unwrap();
-                let unit = parse_length_unit(inner.next().unwrap().as_rule());
+                spans.goal = span_of(&part);
+                let (value, unit) = parse_distance(part);
                 goal = Some(Goal::Distance { value, unit });
             }
+            Rule::first_of_goal => {
+                spans.goal = span_of(&part);
+                goal = Some(parse_first_of_goal(part)?);
+            }
+            Rule::open_goal => {
+                spans.goal = span_of(&part);
+                goal = Some(Goal::Open);
+            }
+            Rule::race_goal => {
+                spans.goal = span_of(&part);
+                let distance = match part.into_inner().next().unwrap().as_rule() {
+                    Rule::half_marathon => RaceDistance::HalfMarathon,
+                    Rule::marathon => RaceDistance::Marathon,
+                    Rule::ten_k => RaceDistance::TenK,
+                    Rule::five_k => RaceDistance::FiveK,
+                    Rule::race_mile => RaceDistance::Mile,
+                    _ => unreachable!(),
+                };
+                goal = Some(Goal::Race { distance });
+            }
             Rule::duration_goal => {
-                let mut inner = part.into_inner();
-                let value = inner.next().unwrap().as_str().parse::<f64>().unwrap();
-                let unit = parse_time_unit(inner.next().unwrap().as_rule());
+                spans.goal = span_of(&part);
+                let (value, unit) = parse_duration(part)?;
                 goal = Some(Goal::Duration { value, unit });
             }
             Rule::heart_rate_alert => {
-                alert = Some(Alert::HeartRate(parse_heart_rate_zone(
+                spans.alerts.push(span_of(&part));
+                alerts.push(Alert::HeartRate(parse_heart_rate_zone(
                     part.into_inner().next().unwrap().as_rule(),
                 )));
             }
+            Rule::heart_rate_zone_range_alert => {
+                spans.alerts.push(span_of(&part));
+                let mut inner = part.into_inner();
+                let min = parse_heart_rate_zone(inner.next().unwrap().as_rule());
+                let max = match inner.next().unwrap().as_str() {
+                    "1" => HeartRateZone::Z1,
+                    "2" => HeartRateZone::Z2,
+                    "3" => HeartRateZone::Z3,
+                    "4" => HeartRateZone::Z4,
+                    "5" => HeartRateZone::Z5,
+                    _ => unreachable!(),
+                };
+                alerts.push(Alert::HeartRateZoneRange { min, max });
+            }
+            Rule::heart_rate_threshold_alert => {
+                spans.alerts.push(span_of(&part));
+                let mut inner = part.into_inner().peekable();
+                let ceiling = inner
+                    .next_if(|p| p.as_rule() == Rule::heart_rate_ceiling)
+                    .is_some();
+                let bpm = parse_bpm(&inner.next().unwrap())?;
+                alerts.push(Alert::HeartRateThreshold { bpm, ceiling });
+            }
+            Rule::heart_rate_range_alert => {
+                spans.alerts.push(span_of(&part));
+                let mut inner = part.into_inner();
+                let min = parse_bpm(&inner.next().unwrap())?;
+                let max = parse_bpm(&inner.next().unwrap())?;
+                alerts.push(Alert::HeartRateRange { min, max });
+            }
+            Rule::power_threshold_alert => {
+                spans.alerts.push(span_of(&part));
+                let watts = parse_watts(&part.into_inner().next().unwrap())?;
+                alerts.push(Alert::PowerThreshold { watts });
+            }
+            Rule::power_range_alert => {
+                spans.alerts.push(span_of(&part));
+                let mut inner = part.into_inner();
+                let min = parse_watts(&inner.next().unwrap())?;
+                let max = parse_watts(&inner.next().unwrap())?;
+                alerts.push(Alert::PowerRange { min, max });
+            }
+            Rule::critical_power_alert => {
+                spans.alerts.push(span_of(&part));
+                let percent = part
+                    .into_inner()
+                    .next()
+                    .unwrap()
+                    .as_str()
+                    .parse::<f64>()
+                    .unwrap();
+                alerts.push(Alert::CriticalPower { percent });
+            }
+            Rule::cadence_threshold_alert => {
+                spans.alerts.push(span_of(&part));
+                let spm = parse_spm(&part.into_inner().next().unwrap())?;
+                alerts.push(Alert::CadenceThreshold { spm });
+            }
+            Rule::cadence_range_alert => {
+                spans.alerts.push(span_of(&part));
+                let mut inner = part.into_inner();
+                let min = parse_spm(&inner.next().unwrap())?;
+                let max = parse_spm(&inner.next().unwrap())?;
+                alerts.push(Alert::CadenceRange { min, max });
+            }
+            Rule::speed_threshold_alert => {
+                spans.alerts.push(span_of(&part));
+                let mut inner = part.into_inner();
+                let value = inner.next().unwrap().as_str().parse::<f64>().unwrap();
+                let unit = parse_speed_unit(inner.next().unwrap().as_rule());
+                alerts.push(Alert::SpeedThreshold(Speed { value, unit }));
+            }
+            Rule::speed_range_alert => {
+                spans.alerts.push(span_of(&part));
+                let mut inner = part.into_inner();
+                let min = inner.next().unwrap().as_str().parse::<f64>().unwrap();
+                let max = inner.next().unwrap().as_str().parse::<f64>().unwrap();
+                let unit = parse_speed_unit(inner.next().unwrap().as_rule());
+                alerts.push(Alert::SpeedRange {
+                    min: Speed { value: min, unit },
+                    max: Speed { value: max, unit },
+                });
+            }
             Rule::pace_threshold_alert => {
-                alert = Some(Alert::PaceThreshold(parse_pace(
+                spans.alerts.push(span_of(&part));
+                alerts.push(Alert::PaceThreshold(parse_pace(
                     part.into_inner().next().unwrap(),
                 )?));
             }
             Rule::pace_range_alert => {
-                alert = Some(parse_pace_range(part)?);
+                spans.alerts.push(span_of(&part));
+                alerts.push(parse_pace_range(part)?);
+            }
+            Rule::training_pace_alert => {
+                spans.alerts.push(span_of(&part));
+                alerts.push(Alert::TrainingPace(parse_training_pace(
+                    part.into_inner().next().unwrap().as_rule(),
+                )));
             }
             _ => unreachable!(),
         }
     }
 
+    source.steps.push(spans);
+
     Ok(WorkoutStep {
         goal: goal.unwrap(),
-        alert,
+        alerts,
     })
 }
 
 fn parse_interval_blocks(
     pairs: Vec<pest::iterators::Pair<Rule>>,
-) -> Result<Vec<IntervalBlock>, pest::error::Error<Rule>> {
+    source: &mut SourceMap,
+) -> Result<Vec<IntervalBlock>, DashError> {
     let mut interval_blocks = Vec::new();
     for pair in pairs {
         match pair.as_rule() {
             Rule::interval_block => {
-                interval_blocks.push(parse_interval_block(pair)?);
+                interval_blocks.push(parse_interval_block(pair, source)?);
             }
             _ => unreachable!(),
         }
@@ -99,69 +257,288 @@ fn parse_interval_blocks(
 
 fn parse_interval_block(
     pair: pest::iterators::Pair<Rule>,
-) -> Result<IntervalBlock, pest::error::Error<Rule>> {
+    source: &mut SourceMap,
+) -> Result<IntervalBlock, DashError> {
     let mut repeats = None;
     let mut steps = Vec::new();
 
+    source.blocks.push(span_of(&pair));
+
     let inner_pairs: Vec<_> = pair.into_inner().collect();
 
     if inner_pairs.len() > 1 {
-        repeats = Some(inner_pairs[0].as_str().parse::<u32>().unwrap());
+        let number = &inner_pairs[0];
+        repeats = Some(number.as_str().parse::<u32>().map_err(|_| {
+            DashError::at(
+                number,
+                ErrorCode::NumberOutOfRange,
+                format!("Repeat count {} is too large", number.as_str()),
+            )
+        })?);
         let reps_pair = inner_pairs[1].clone();
-        for step in reps_pair.into_inner() {
-            steps.push(parse_interval_step(step)?);
+        for rep in reps_pair.into_inner() {
+            steps.push(parse_interval_rep(rep, source)?);
         }
     } else {
-        steps.push(parse_interval_step(inner_pairs[0].clone())?);
+        steps.push(IntervalRep::Step(parse_interval_step(
+            inner_pairs[0].clone(),
+            source,
+        )?));
     }
 
     Ok(IntervalBlock { repeats, steps })
 }
 
+fn parse_interval_rep(
+    pair: pest::iterators::Pair<Rule>,
+    source: &mut SourceMap,
+) -> Result<IntervalRep, DashError> {
+    // A plain step inside a repeat is kept as a step, a nested `n x (...)`
+    // becomes a block of its own
+    let mut inner = pair.clone().into_inner();
+    match (inner.next(), inner.next()) {
+        (Some(step), None) => Ok(IntervalRep::Step(parse_interval_step(step, source)?)),
+        _ => Ok(IntervalRep::Block(parse_interval_block(pair, source)?)),
+    }
+}
+
 fn parse_interval_step(
     pair: pest::iterators::Pair<Rule>,
-) -> Result<IntervalStep, pest::error::Error<Rule>> {
+    source: &mut SourceMap,
+) -> Result<IntervalStep, DashError> {
     let mut inner = pair.into_inner();
-    let step = parse_workout_step(inner.next().unwrap())?;
+    let step = parse_workout_step(inner.next().unwrap(), source)?;
     let has_recovery = inner.next().is_some();
 
     Ok(IntervalStep { step, has_recovery })
 }
 
-fn parse_time_str(time_str: &str) -> Result<f64, pest::error::Error<Rule>> {
-    let parts: Vec<&str> = time_str.split(':').collect();
+fn parse_bpm(pair: &pest::iterators::Pair<Rule>) -> Result<u32, DashError> {
+    pair.as_str().parse::<u32>().map_err(|_| {
+        DashError::at(
+            pair,
+            ErrorCode::NumberOutOfRange,
+            format!("Heart rate {} is too large", pair.as_str()),
+        )
+    })
+}
+
+fn parse_watts(pair: &pest::iterators::Pair<Rule>) -> Result<u32, DashError> {
+    pair.as_str().parse::<u32>().map_err(|_| {
+        DashError::at(
+            pair,
+            ErrorCode::NumberOutOfRange,
+            format!("Power {}W is too large", pair.as_str()),
+        )
+    })
+}
+
+fn parse_spm(pair: &pest::iterators::Pair<Rule>) -> Result<u32, DashError> {
+    pair.as_str().parse::<u32>().map_err(|_| {
+        DashError::at(
+            pair,
+            ErrorCode::NumberOutOfRange,
+            format!("Cadence {}spm is too large", pair.as_str()),
+        )
+    })
+}
+
+fn parse_distance(pair: pest::iterators::Pair<Rule>) -> (f64, LengthUnit) {
+    let mut inner = pair.into_inner();
+    let value = inner.next().unwrap().as_str().parse::<f64>().unwrap();
+    let unit = parse_length_unit(inner.next().unwrap().as_rule());
+    (value, unit)
+}
+
+/// `1 mile or 8 min`, in either order
+fn parse_first_of_goal(pair: pest::iterators::Pair<Rule>) -> Result<Goal, DashError> {
+    let mut distance = None;
+    let mut duration = None;
+    for part in pair.into_inner() {
+        match part.as_rule() {
+            Rule::distance_goal => distance = Some(parse_distance(part)),
+            Rule::duration_goal => duration = Some(parse_duration(part)?),
+            _ => unreachable!(),
+        }
+    }
+
+    // The grammar requires both limits
+    let (distance, length_unit) = distance.unwrap();
+    let (duration, time_unit) = duration.unwrap();
+    Ok(Goal::FirstOf {
+        distance,
+        length_unit,
+        duration,
+        time_unit,
+    })
+}
+
+fn parse_duration(pair: pest::iterators::Pair<Rule>) -> Result<(f64, TimeUnit), DashError> {
+    let mut inner = pair.into_inner();
+    let first = inner.next().unwrap();
+    let seconds = match first.as_rule() {
+        Rule::clock_duration => parse_clock_duration(&first)?,
+        Rule::compact_duration => parse_compact_duration(first)?,
+        _ => {
+            let value = first.as_str().parse::<f64>().unwrap();
+            let unit = parse_time_unit(inner.next().unwrap().as_rule());
+            return Ok((value, unit));
+        }
+    };
+
+    Ok(exact_duration(seconds))
+}
+
+/// Duration in the largest unit that still holds it exactly
+pub(crate) fn exact_duration(seconds: u64) -> (f64, TimeUnit) {
+    let (value, unit) = if seconds.is_multiple_of(3600) {
+        (seconds / 3600, TimeUnit::Hours)
+    } else if seconds.is_multiple_of(60) {
+        (seconds / 60, TimeUnit::Minutes)
+    } else {
+        (seconds, TimeUnit::Seconds)
+    };
+    (value as f64, unit)
+}
+
+/// `h:mm:ss` or `mm:ss`, in seconds
+fn parse_clock_duration(pair: &pest::iterators::Pair<Rule>) -> Result<u64, DashError> {
+    let parts: Vec<u64> = pair
+        .as_str()
+        .split(':')
+        .map(|part| parse_duration_part(pair, part))
+        .collect::<Result<_, _>>()?;
+
+    // Only the leading part may go past 59
+    if parts[1..].iter().any(|part| *part >= 60) {
+        return Err(DashError::at(
+            pair,
+            ErrorCode::InvalidDuration,
+            format!(
+                "Duration {} has more than 59 minutes or seconds",
+                pair.as_str()
+            ),
+        ));
+    }
+    parts
+        .iter()
+        .try_fold(0u64, |total, part| {
+            total.checked_mul(60)?.checked_add(*part)
+        })
+        .ok_or_else(|| {
+            DashError::at(
+                pair,
+                ErrorCode::NumberOutOfRange,
+                format!("Duration {} is too long", pair.as_str()),
+            )
+        })
+}
+
+/// `1h15m`, `15m30s` or `90s`, in seconds
+fn parse_compact_duration(pair: pest::iterators::Pair<Rule>) -> Result<u64, DashError> {
+    let mut seconds: u64 = 0;
+    for part in pair.clone().into_inner() {
+        let text = part.as_str();
+        let value = parse_duration_part(&part, &text[..text.len() - 1])?;
+        let scale = match part.as_rule() {
+            Rule::compact_hours => 3600,
+            Rule::compact_minutes => 60,
+            Rule::compact_seconds => 1,
+            _ => unreachable!(),
+        };
+        seconds = value
+            .checked_mul(scale)
+            .and_then(|value| seconds.checked_add(value))
+            .ok_or_else(|| {
+                DashError::at(
+                    &pair,
+                    ErrorCode::NumberOutOfRange,
+                    format!("Duration {} is too long", pair.as_str()),
+                )
+            })?;
+    }
+    Ok(seconds)
+}
+
+fn parse_duration_part(pair: &pest::iterators::Pair<Rule>, part: &str) -> Result<u64, DashError> {
+    part.parse::<u64>().map_err(|_| {
+        DashError::at(
+            pair,
+            ErrorCode::NumberOutOfRange,
+            format!("Duration {} is too long", pair.as_str()),
+        )
+    })
+}
+
+fn parse_time_str(pair: &pest::iterators::Pair<Rule>) -> Result<f64, DashError> {
+    if pair.as_rule() == Rule::pace_seconds {
+        return Ok(pair.as_str().parse::<f64>().unwrap());
+    }
+    if let Some(seconds) = pair.as_str().strip_suffix('s') {
+        return Ok(seconds.parse::<f64>().unwrap());
+    }
+
+    let parts: Vec<&str> = pair.as_str().split(':').collect();
     let minutes = parts[0].parse::<f64>().unwrap();
     let seconds = parts[1].parse::<f64>().unwrap();
+    if seconds >= 60.0 {
+        return Err(DashError::at(
+            pair,
+            ErrorCode::InvalidPaceSeconds,
+            format!("Pace {} has more than 59 seconds", pair.as_str()),
+        ));
+    }
     let total_seconds = minutes * 60.0 + seconds;
     Ok(total_seconds)
 }
 
-fn parse_pace_range(pair: pest::iterators::Pair<Rule>) -> Result<Alert, pest::error::Error<Rule>> {
-    let mut inner = pair.into_inner();
-    let first_time_str = inner.next().unwrap().as_str();
-    let second_time_str = inner.next().unwrap().as_str();
+/// Distance and unit a pace is given over, `400m` in `1:30/400m`, one unit when omitted
+fn parse_pace_split(
+    inner: &mut pest::iterators::Pairs<Rule>,
+) -> Result<(f64, LengthUnit), DashError> {
+    let pair = inner.next().unwrap();
+    if pair.as_rule() != Rule::pace_split {
+        return Ok((1.0, parse_length_unit(pair.as_rule())));
+    }
+
+    let split = pair.as_str().parse::<f64>().unwrap();
+    if split <= 0.0 {
+        return Err(DashError::at(
+            &pair,
+            ErrorCode::ZeroPaceSplit,
+            format!("Pace is given over a distance of {}", pair.as_str()),
+        ));
+    }
     let unit = parse_length_unit(inner.next().unwrap().as_rule());
+    Ok((split, unit))
+}
+
+fn parse_pace_range(pair: pest::iterators::Pair<Rule>) -> Result<Alert, DashError> {
+    let mut inner = pair.into_inner();
+    let first_time = inner.next().unwrap();
+    let second_time = inner.next().unwrap();
+    let (split, unit) = parse_pace_split(&mut inner)?;
     Ok(Alert::PaceRange {
         min: Pace {
-            time: parse_time_str(first_time_str)?,
+            time: parse_time_str(&first_time)? / split,
             unit,
         },
         max: Pace {
-            time: parse_time_str(second_time_str)?,
+            time: parse_time_str(&second_time)? / split,
             unit,
         },
     })
 }
 
-fn parse_pace(pair: pest::iterators::Pair<Rule>) -> Result<Pace, pest::error::Error<Rule>> {
+fn parse_pace(pair: pest::iterators::Pair<Rule>) -> Result<Pace, DashError> {
     let mut inner = pair.into_inner();
-    let time_str = inner.next().unwrap().as_str();
-    let unit = parse_length_unit(inner.next().unwrap().as_rule());
+    let time = inner.next().unwrap();
+    let (split, unit) = parse_pace_split(&mut inner)?;
 
-    let total_seconds = parse_time_str(time_str)?;
+    let total_seconds = parse_time_str(&time)?;
 
     Ok(Pace {
-        time: total_seconds,
+        time: total_seconds / split,
         unit,
     })
 }
@@ -177,6 +554,14 @@ fn parse_length_unit(rule: Rule) -> LengthUnit {
     }
 }
 
+fn parse_speed_unit(rule: Rule) -> SpeedUnit {
+    match rule {
+        Rule::kilometers_per_hour => SpeedUnit::KilometersPerHour,
+        Rule::miles_per_hour => SpeedUnit::MilesPerHour,
+        _ => unreachable!(),
+    }
+}
+
 fn parse_time_unit(rule: Rule) -> TimeUnit {
     match rule {
         Rule::seconds => TimeUnit::Seconds,
@@ -197,5 +582,17 @@ fn parse_heart_rate_zone(rule: Rule) -> HeartRateZone {
     }
 }
 
+fn parse_training_pace(rule: Rule) -> TrainingPace {
+    match rule {
+        Rule::easy_pace => TrainingPace::Easy,
+        Rule::marathon_pace => TrainingPace::Marathon,
+        Rule::half_marathon_pace => TrainingPace::HalfMarathon,
+        Rule::threshold_pace => TrainingPace::Threshold,
+        Rule::interval_pace => TrainingPace::Interval,
+        Rule::repetition_pace => TrainingPace::Repetition,
+        _ => unreachable!(),
+    }
+}
+
 #[cfg(test)]
 mod tests;
@@ -1,6 +1,14 @@
 use super::*;
+use crate::error::ErrorCode;
 use wasm_bindgen_test::*;
 
+fn step(rep: &IntervalRep) -> &IntervalStep {
+    match rep {
+        IntervalRep::Step(step) => step,
+        IntervalRep::Block(_) => panic!("expected a step, got a block"),
+    }
+}
+
 #[wasm_bindgen_test(unsupported = test)]
 fn test_parse_workout() {
     let input = "1 mile warmup + 3 x (0.5 miles @z3 + 1 mile recovery) + 0.5 miles cooldown";
@@ -18,12 +26,12 @@ fn test_parse_workout() {
     assert_eq!(interval.steps.len(), 2);
 
     // Check that the first step has a z3 alert
-    if let Some(Alert::HeartRate(zone)) = &interval.steps[0].step.alert {
+    if let Some(Alert::HeartRate(zone)) = &step(&interval.steps[0]).step.alerts.first() {
         assert!(matches!(zone, HeartRateZone::Z3));
     }
 
     // Check that the second step has recovery
-    assert!(interval.steps[1].has_recovery);
+    assert!(step(&interval.steps[1]).has_recovery);
 }
 
 #[wasm_bindgen_test(unsupported = test)]
@@ -63,8 +71,8 @@ fn test_parse_workout_with_pace_threshold() {
     assert!(result.is_ok());
 
     let workout = result.unwrap();
-    let step = &workout.intervals[0].steps[0].step;
-    match &step.alert {
+    let step = &step(&workout.intervals[0].steps[0]).step;
+    match step.alerts.first() {
         Some(Alert::PaceThreshold(pace)) => {
             assert_eq!(pace.time, 450.0); // 7:30 = 450 seconds
             assert!(matches!(pace.unit, LengthUnit::Miles));
@@ -73,6 +81,52 @@ fn test_parse_workout_with_pace_threshold() {
     }
 }
 
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_pace_split() {
+    let input = "8 x (400m @72s/400m) + 4 x (800m @2:50-3:00/800m) + 1 mile @1:30/400m";
+    let workout = parse_workout(input).unwrap();
+
+    // Paces are stored per one unit
+    assert_eq!(
+        step(&workout.intervals[0].steps[0]).step.alerts,
+        vec![Alert::PaceThreshold(Pace {
+            time: 72.0 / 400.0,
+            unit: LengthUnit::Meters
+        })]
+    );
+    assert_eq!(
+        step(&workout.intervals[1].steps[0]).step.alerts,
+        vec![Alert::PaceRange {
+            min: Pace {
+                time: 170.0 / 800.0,
+                unit: LengthUnit::Meters
+            },
+            max: Pace {
+                time: 180.0 / 800.0,
+                unit: LengthUnit::Meters
+            }
+        }]
+    );
+    assert_eq!(
+        step(&workout.intervals[2].steps[0]).step.alerts,
+        vec![Alert::PaceThreshold(Pace {
+            time: 90.0 / 400.0,
+            unit: LengthUnit::Meters
+        })]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_zero_pace_split() {
+    let input = "1 mile @1:30/0m";
+    let error = parse_workout(input).unwrap_err();
+    assert_eq!(error.code, ErrorCode::ZeroPaceSplit);
+    assert_eq!(
+        &input[error.span.start as usize..error.span.end as usize],
+        "0"
+    );
+}
+
 #[wasm_bindgen_test(unsupported = test)]
 fn test_parse_workout_with_pace_range() {
     let input = "1 mile @7:30-8:00/mile";
@@ -80,8 +134,8 @@ fn test_parse_workout_with_pace_range() {
     assert!(result.is_ok());
 
     let workout = result.unwrap();
-    let step = &workout.intervals[0].steps[0].step;
-    match &step.alert {
+    let step = &step(&workout.intervals[0].steps[0]).step;
+    match step.alerts.first() {
         Some(Alert::PaceRange { min, max }) => {
             assert_eq!(min.time, 450.0); // 7:30 = 450 seconds
             assert_eq!(max.time, 480.0); // 8:00 = 480 seconds
@@ -110,7 +164,7 @@ fn test_parse_workout_with_all_heart_rate_zones() {
     ];
 
     for (interval, expected_zone) in workout.intervals.iter().zip(expected_zones.iter()) {
-        match &interval.steps[0].step.alert {
+        match &step(&interval.steps[0]).step.alerts.first() {
             Some(Alert::HeartRate(zone)) => assert_eq!(zone, expected_zone),
             _ => unreachable!(),
         }
@@ -135,7 +189,7 @@ fn test_parse_workout_with_different_length_units() {
     ];
 
     for (interval, expected_unit) in workout.intervals.iter().zip(expected_units.iter()) {
-        match &interval.steps[0].step.goal {
+        match &step(&interval.steps[0]).step.goal {
             Goal::Distance { unit, .. } => assert_eq!(unit, expected_unit),
             _ => unreachable!(),
         }
@@ -154,13 +208,168 @@ fn test_parse_workout_with_different_time_units() {
     let expected_units = [TimeUnit::Seconds, TimeUnit::Minutes, TimeUnit::Hours];
 
     for (interval, expected_unit) in workout.intervals.iter().zip(expected_units.iter()) {
-        match &interval.steps[0].step.goal {
+        match &step(&interval.steps[0]).step.goal {
             Goal::Duration { unit, .. } => assert_eq!(unit, expected_unit),
             _ => unreachable!(),
         }
     }
 }
 
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_clock_durations() {
+    let input = "1:15:00 warmup + 4 x (4:30 + 1h15m30s + 15m30s + 90s recovery) + 1h cooldown";
+    let workout = parse_workout(input).unwrap();
+
+    let goal = |value, unit| Goal::Duration { value, unit };
+    assert_eq!(workout.warmup.unwrap().goal, goal(75.0, TimeUnit::Minutes));
+    let goals: Vec<Goal> = workout.intervals[0]
+        .steps
+        .iter()
+        .map(|rep| step(rep).step.goal.clone())
+        .collect();
+    assert_eq!(
+        goals,
+        vec![
+            goal(270.0, TimeUnit::Seconds),
+            goal(4530.0, TimeUnit::Seconds),
+            goal(930.0, TimeUnit::Seconds),
+            goal(90.0, TimeUnit::Seconds),
+        ]
+    );
+    assert_eq!(workout.cooldown.unwrap().goal, goal(1.0, TimeUnit::Hours));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_compact_units_stay_distinct() {
+    // A lone `m` is still meters, and full unit names are not split up
+    let workout = parse_workout("15m + 90sec + 1hr").unwrap();
+    let goals: Vec<Goal> = workout
+        .intervals
+        .iter()
+        .map(|block| step(&block.steps[0]).step.goal.clone())
+        .collect();
+    assert_eq!(
+        goals,
+        vec![
+            Goal::Distance {
+                value: 15.0,
+                unit: LengthUnit::Meters
+            },
+            Goal::Duration {
+                value: 90.0,
+                unit: TimeUnit::Seconds
+            },
+            Goal::Duration {
+                value: 1.0,
+                unit: TimeUnit::Hours
+            },
+        ]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_invalid_clock_duration() {
+    let input = "1 mile + 1:75:00";
+    let error = parse_workout(input).unwrap_err();
+    assert_eq!(error.code, ErrorCode::InvalidDuration);
+    assert_eq!(
+        &input[error.span.start as usize..error.span.end as usize],
+        "1:75:00"
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_clock_duration_too_long() {
+    let error = parse_workout("999999999999999999:00").unwrap_err();
+    assert_eq!(error.code, ErrorCode::NumberOutOfRange);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_race_distances() {
+    let input = "mile warmup + 5k + 10K @z4 + hm + Half Marathon race + marathon + 5 km";
+    let workout = parse_workout(input).unwrap();
+
+    assert_eq!(
+        workout.warmup.unwrap().goal,
+        Goal::Race {
+            distance: RaceDistance::Mile
+        }
+    );
+    let goals: Vec<Goal> = workout
+        .intervals
+        .iter()
+        .map(|block| step(&block.steps[0]).step.goal.clone())
+        .collect();
+    let race = |distance| Goal::Race { distance };
+    assert_eq!(
+        goals,
+        vec![
+            race(RaceDistance::FiveK),
+            race(RaceDistance::TenK),
+            race(RaceDistance::HalfMarathon),
+            race(RaceDistance::HalfMarathon),
+            race(RaceDistance::Marathon),
+            Goal::Distance {
+                value: 5.0,
+                unit: LengthUnit::Kilometers
+            },
+        ]
+    );
+    assert_eq!(
+        RaceDistance::HalfMarathon.length(),
+        (21.0975, LengthUnit::Kilometers)
+    );
+
+    // `race` only follows a race distance
+    let workout = parse_workout("2 mile warmup + 5k race @z5").unwrap();
+    assert_eq!(
+        workout.intervals[0].steps[0],
+        parse_workout("5k @z5").unwrap().intervals[0].steps[0]
+    );
+    assert!(parse_workout("5 km race").is_err());
+    assert!(parse_workout("5k racing").is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_open_goals() {
+    let workout = parse_workout("open warmup + 4 x (1 km @z4 + lap @z1 recovery)").unwrap();
+    assert_eq!(workout.warmup.unwrap().goal, Goal::Open);
+    let recovery = step(&workout.intervals[0].steps[1]);
+    assert_eq!(recovery.step.goal, Goal::Open);
+    assert!(recovery.has_recovery);
+
+    assert!(parse_workout("opening").is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_first_of_goals() {
+    let workout = parse_workout("1 mile or 8 min @z4 + 10:00 or 2 km").unwrap();
+    let goals: Vec<Goal> = workout
+        .intervals
+        .iter()
+        .map(|block| step(&block.steps[0]).step.goal.clone())
+        .collect();
+    assert_eq!(
+        goals,
+        vec![
+            Goal::FirstOf {
+                distance: 1.0,
+                length_unit: LengthUnit::Miles,
+                duration: 8.0,
+                time_unit: TimeUnit::Minutes
+            },
+            Goal::FirstOf {
+                distance: 2.0,
+                length_unit: LengthUnit::Kilometers,
+                duration: 10.0,
+                time_unit: TimeUnit::Minutes
+            },
+        ]
+    );
+
+    assert!(parse_workout("1 mile or 2 km").is_err());
+}
+
 #[wasm_bindgen_test(unsupported = test)]
 fn test_parse_workout_with_multiple_intervals() {
     let input = "1 mile warmup + 3 x (0.5 miles @z3 + 1 mile recovery) + 2 x (1 mile @z4) + 0.5 miles cooldown";
@@ -174,7 +383,7 @@ fn test_parse_workout_with_multiple_intervals() {
     let first_interval = &workout.intervals[0];
     assert_eq!(first_interval.repeats, Some(3));
     assert_eq!(first_interval.steps.len(), 2);
-    match &first_interval.steps[0].step.alert {
+    match &step(&first_interval.steps[0]).step.alerts.first() {
         Some(Alert::HeartRate(zone)) => assert!(matches!(zone, HeartRateZone::Z3)),
         _ => unreachable!(),
     }
@@ -183,12 +392,244 @@ fn test_parse_workout_with_multiple_intervals() {
     let second_interval = &workout.intervals[1];
     assert_eq!(second_interval.repeats, Some(2));
     assert_eq!(second_interval.steps.len(), 1);
-    match &second_interval.steps[0].step.alert {
+    match &step(&second_interval.steps[0]).step.alerts.first() {
         Some(Alert::HeartRate(zone)) => assert!(matches!(zone, HeartRateZone::Z4)),
         _ => unreachable!(),
     }
 }
 
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_nested_repeats() {
+    let input = "3 x (4 x (200m @z5 + 200m recovery) + 3 min recovery)";
+    let result = parse_workout(input);
+    assert!(result.is_ok());
+
+    let workout = result.unwrap();
+    assert_eq!(workout.intervals.len(), 1);
+
+    let outer = &workout.intervals[0];
+    assert_eq!(outer.repeats, Some(3));
+    assert_eq!(outer.steps.len(), 2);
+
+    // Check the nested block
+    match &outer.steps[0] {
+        IntervalRep::Block(inner) => {
+            assert_eq!(inner.repeats, Some(4));
+            assert_eq!(inner.steps.len(), 2);
+            assert!(!step(&inner.steps[0]).has_recovery);
+            assert!(step(&inner.steps[1]).has_recovery);
+        }
+        _ => unreachable!(),
+    }
+
+    // Check the recovery after the nested block
+    assert!(step(&outer.steps[1]).has_recovery);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_deeply_nested_repeats() {
+    let input = "1 mile warmup + 2 x (2 x (2 x (100m @z5 + 100m recovery))) + 1 mile cooldown";
+    let result = parse_workout(input);
+    assert!(result.is_ok());
+
+    let workout = result.unwrap();
+    let mut block = &workout.intervals[0];
+    for _ in 0..2 {
+        assert_eq!(block.repeats, Some(2));
+        block = match &block.steps[0] {
+            IntervalRep::Block(inner) => inner,
+            _ => unreachable!(),
+        };
+    }
+    assert_eq!(block.repeats, Some(2));
+    assert_eq!(block.steps.len(), 2);
+    assert!(workout.cooldown.is_some());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_zone_ranges() {
+    let input = "30 min @z1-z2 + 20 min @z2-3";
+    let workout = parse_workout(input).unwrap();
+    assert_eq!(
+        step(&workout.intervals[0].steps[0]).step.alerts,
+        vec![Alert::HeartRateZoneRange {
+            min: HeartRateZone::Z1,
+            max: HeartRateZone::Z2
+        }]
+    );
+    assert_eq!(
+        step(&workout.intervals[1].steps[0]).step.alerts,
+        vec![Alert::HeartRateZoneRange {
+            min: HeartRateZone::Z2,
+            max: HeartRateZone::Z3
+        }]
+    );
+    assert!(parse_workout("30 min @z2-z6").is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_heart_rate_bpm() {
+    let input = "20 min @150bpm + 10 min @150-160bpm + 30 min @<145bpm";
+    let result = parse_workout(input);
+    assert!(result.is_ok());
+
+    let workout = result.unwrap();
+    let alerts: Vec<_> = workout
+        .intervals
+        .iter()
+        .map(|interval| step(&interval.steps[0]).step.alerts[0].clone())
+        .collect();
+    assert_eq!(
+        alerts,
+        vec![
+            Alert::HeartRateThreshold {
+                bpm: 150,
+                ceiling: false
+            },
+            Alert::HeartRateRange { min: 150, max: 160 },
+            Alert::HeartRateThreshold {
+                bpm: 145,
+                ceiling: true
+            },
+        ]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_power() {
+    let input = "5 min @260-280W + 3 x (3 min @105%CP) + 10 min @200w + 1 mile @92.5%FTP";
+    let result = parse_workout(input);
+    assert!(result.is_ok());
+
+    let workout = result.unwrap();
+    let alerts: Vec<_> = workout
+        .intervals
+        .iter()
+        .map(|interval| step(&interval.steps[0]).step.alerts[0].clone())
+        .collect();
+    assert_eq!(
+        alerts,
+        vec![
+            Alert::PowerRange { min: 260, max: 280 },
+            Alert::CriticalPower { percent: 105.0 },
+            Alert::PowerThreshold { watts: 200 },
+            Alert::CriticalPower { percent: 92.5 },
+        ]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_cadence() {
+    let input = "5 min @180spm + 4 x (30 sec @175-185spm)";
+    let result = parse_workout(input);
+    assert!(result.is_ok());
+
+    let workout = result.unwrap();
+    assert_eq!(
+        step(&workout.intervals[0].steps[0]).step.alerts,
+        vec![Alert::CadenceThreshold { spm: 180 }]
+    );
+    assert_eq!(
+        step(&workout.intervals[1].steps[0]).step.alerts,
+        vec![Alert::CadenceRange { min: 175, max: 185 }]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_speed() {
+    let input = "20 min @12km/h + 4 x (1 mile @7.5-8mph)";
+    let result = parse_workout(input);
+    assert!(result.is_ok());
+
+    let workout = result.unwrap();
+    assert_eq!(
+        step(&workout.intervals[0].steps[0]).step.alerts,
+        vec![Alert::SpeedThreshold(Speed {
+            value: 12.0,
+            unit: SpeedUnit::KilometersPerHour
+        })]
+    );
+    assert_eq!(
+        step(&workout.intervals[1].steps[0]).step.alerts,
+        vec![Alert::SpeedRange {
+            min: Speed {
+                value: 7.5,
+                unit: SpeedUnit::MilesPerHour
+            },
+            max: Speed {
+                value: 8.0,
+                unit: SpeedUnit::MilesPerHour
+            }
+        }]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_training_paces() {
+    let input = "2 mi @E + 4 x (1 mile @T + 1 min recovery) + 10 mi @MP + 5k @HMP + 3 x (1 km @I + 200m @R)";
+    let workout = parse_workout(input).unwrap();
+
+    let alerts: Vec<Alert> = workout
+        .intervals
+        .iter()
+        .flat_map(|block| block.steps.iter())
+        .flat_map(|rep| step(rep).step.alerts.clone())
+        .collect();
+    assert_eq!(
+        alerts,
+        vec![
+            Alert::TrainingPace(TrainingPace::Easy),
+            Alert::TrainingPace(TrainingPace::Threshold),
+            Alert::TrainingPace(TrainingPace::Marathon),
+            Alert::TrainingPace(TrainingPace::HalfMarathon),
+            Alert::TrainingPace(TrainingPace::Interval),
+            Alert::TrainingPace(TrainingPace::Repetition),
+        ]
+    );
+
+    // Paces are written in capitals and do not run into other words
+    assert!(parse_workout("1 mile @mp").is_err());
+    assert!(parse_workout("1 mile @TP").is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_multiple_alerts() {
+    let input = "1 km @4:00-4:10/km @z4 + 5 min @180spm @<150bpm @250W";
+    let result = parse_workout(input);
+    assert!(result.is_ok());
+
+    let workout = result.unwrap();
+    let kinds: Vec<_> = step(&workout.intervals[0].steps[0])
+        .step
+        .alerts
+        .iter()
+        .map(|alert| alert.kind())
+        .collect();
+    assert_eq!(kinds, vec![AlertKind::Pace, AlertKind::HeartRate]);
+
+    let kinds: Vec<_> = step(&workout.intervals[1].steps[0])
+        .step
+        .alerts
+        .iter()
+        .map(|alert| alert.kind())
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![AlertKind::Cadence, AlertKind::HeartRate, AlertKind::Power]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_with_duplicate_alert_kind() {
+    let input = "1 km @z4 @150-160bpm";
+    let error = parse_workout(input).unwrap_err();
+    assert_eq!(error.code, ErrorCode::DuplicateAlert);
+    assert_eq!(
+        &input[error.span.start as usize..error.span.end as usize],
+        "150-160bpm"
+    );
+}
+
 #[wasm_bindgen_test(unsupported = test)]
 fn test_parse_workout_with_invalid_input() {
     let invalid_inputs = [
@@ -198,6 +639,11 @@ fn test_parse_workout_with_invalid_input() {
         "1 @z3",                       // Missing unit
         "1 mile @7:invalid/mile",      // Invalid pace format
         "1 mile @7:30-8:invalid/mile", // Invalid pace range format
+        "1 mile @150",                 // Missing bpm
+        "1 mile @>150bpm",             // Invalid heart rate bound
+        "1 mile @105%",                // Missing critical power
+        "1 mile @260-W",               // Invalid power range
+        "1 mile @180-spm",             // Invalid cadence range
     ];
 
     for input in invalid_inputs {
@@ -205,3 +651,52 @@ fn test_parse_workout_with_invalid_input() {
         assert!(result.is_err(), "Expected error for input: {}", input);
     }
 }
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_error_position() {
+    let input = "1 mile warmup +\n3 x (400m @z9)";
+    let error = parse_workout(input).unwrap_err();
+
+    assert!(matches!(error.code, ErrorCode::UnexpectedToken));
+    assert_eq!(error.line, 2);
+    assert_eq!(error.column, 12);
+    assert_eq!(
+        &input[error.span.start as usize..error.span.end as usize],
+        "z9"
+    );
+    assert!(error.expected.contains(&"a heart rate zone".to_string()));
+    assert!(error.expected.contains(&"a pace".to_string()));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_error_expected_words() {
+    let error = parse_workout("1 @z3").unwrap_err();
+    assert_eq!(error.expected, vec!["a distance unit", "a time unit"]);
+    assert_eq!(error.message, "expected a distance unit or a time unit");
+
+    let error = parse_workout("1 mile +").unwrap_err();
+    assert!(matches!(error.code, ErrorCode::UnexpectedEnd));
+    assert_eq!(error.span.start, 8);
+    assert_eq!(error.span.end, 8);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_repeat_overflow() {
+    let input = "99999999999 x (400m @z5)";
+    let error = parse_workout(input).unwrap_err();
+    assert_eq!(error.code, ErrorCode::NumberOutOfRange);
+    assert_eq!(error.span.start, 0);
+    assert_eq!(error.span.end, 11);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_parse_workout_invalid_pace_seconds() {
+    let input = "1 mile @7:75/mile";
+    let error = parse_workout(input).unwrap_err();
+    assert_eq!(error.code, ErrorCode::InvalidPaceSeconds);
+    assert_eq!(
+        &input[error.span.start as usize..error.span.end as usize],
+        "7:75"
+    );
+    assert_eq!(error.column, 9);
+}
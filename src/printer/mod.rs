@@ -0,0 +1,339 @@
+use std::fmt;
+
+use crate::mileage::{length_unit_to_miles, speed_unit_to_length_unit};
+use crate::models::*;
+
+pub fn to_dsl(workout: &Workout) -> String {
+    workout.to_string()
+}
+
+impl fmt::Display for Workout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(warmup) = &self.warmup {
+            write!(f, "{} warmup + ", warmup)?;
+        }
+
+        for (i, interval) in self.intervals.iter().enumerate() {
+            if i > 0 {
+                write!(f, " + ")?;
+            }
+            write!(f, "{}", interval)?;
+        }
+
+        if let Some(cooldown) = &self.cooldown {
+            write!(f, " + {} cooldown", cooldown)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for IntervalBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(repeats) = self.repeats {
+            write!(f, "{} x (", repeats)?;
+        }
+
+        for (i, rep) in self.steps.iter().enumerate() {
+            if i > 0 {
+                write!(f, " + ")?;
+            }
+            write!(f, "{}", rep)?;
+        }
+
+        if self.repeats.is_some() {
+            write!(f, ")")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for IntervalRep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntervalRep::Step(step) => write!(f, "{}", step),
+            IntervalRep::Block(block) => write!(f, "{}", block),
+        }
+    }
+}
+
+impl fmt::Display for IntervalStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.step)?;
+        if self.has_recovery {
+            write!(f, " recovery")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for WorkoutStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.goal)?;
+        for alert in &self.alerts {
+            write!(f, " @{}", alert)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Goal::Distance { value, unit } => write!(f, "{} {}", value, unit),
+            Goal::Duration { value, unit } => write!(f, "{} {}", value, unit),
+            Goal::Race { distance } => write!(f, "{}", distance),
+            Goal::Open => write!(f, "open"),
+            Goal::FirstOf {
+                distance,
+                length_unit,
+                duration,
+                time_unit,
+            } => write!(
+                f,
+                "{} {} or {} {}",
+                distance, length_unit, duration, time_unit
+            ),
+        }
+    }
+}
+
+impl fmt::Display for RaceDistance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let distance = match self {
+            RaceDistance::Mile => "mile",
+            RaceDistance::FiveK => "5k",
+            RaceDistance::TenK => "10k",
+            RaceDistance::HalfMarathon => "half marathon",
+            RaceDistance::Marathon => "marathon",
+        };
+        write!(f, "{}", distance)
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Alert::HeartRate(zone) => write!(f, "{}", zone),
+            Alert::HeartRateZoneRange { min, max } => write!(f, "{}-{}", min, max),
+            Alert::TrainingPace(pace) => write!(f, "{}", pace),
+            Alert::HeartRateThreshold { bpm, ceiling } => {
+                write!(f, "{}{}bpm", if *ceiling { "<" } else { "" }, bpm)
+            }
+            Alert::HeartRateRange { min, max } => write!(f, "{}-{}bpm", min, max),
+            Alert::PowerThreshold { watts } => write!(f, "{}W", watts),
+            Alert::PowerRange { min, max } => write!(f, "{}-{}W", min, max),
+            Alert::CriticalPower { percent } => write!(f, "{}%CP", percent),
+            Alert::CadenceThreshold { spm } => write!(f, "{}spm", spm),
+            Alert::CadenceRange { min, max } => write!(f, "{}-{}spm", min, max),
+            Alert::SpeedThreshold(speed) => write!(f, "{}", speed),
+            // The grammar only has one unit for both ends of a range
+            Alert::SpeedRange { min, max } => {
+                let max_value = match max.unit == min.unit {
+                    true => max.value,
+                    // Distance over time, so the units swap places
+                    false => converted(
+                        max.value,
+                        speed_unit_to_length_unit(min.unit),
+                        speed_unit_to_length_unit(max.unit),
+                    ),
+                };
+                write!(f, "{}-{}{}", min.value, max_value, min.unit)
+            }
+            Alert::PaceThreshold(pace) => write!(f, "{}", pace),
+            // The grammar only has one unit for both ends of a range
+            Alert::PaceRange { min, max } => {
+                let max_time = match max.unit == min.unit {
+                    true => max.time,
+                    false => converted(max.time, max.unit, min.unit),
+                };
+                let split = pace_split(min.unit, &[min.time, max_time]);
+                write!(
+                    f,
+                    "{}-{}/{}",
+                    PaceTime::over(min.time, split),
+                    PaceTime::over(max_time, split),
+                    PaceSplit(split.unwrap_or(1.0), min.unit)
+                )
+            }
+        }
+    }
+}
+
+/// Converts a time per `from` unit to a time per `to` unit, rounded to the microsecond,
+/// past the precision of unit conversions
+fn converted(value: f64, from: LengthUnit, to: LengthUnit) -> f64 {
+    let value = value / length_unit_to_miles(from) * length_unit_to_miles(to);
+    (value * 1e6).round() / 1e6
+}
+
+impl fmt::Display for Pace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let split = pace_split(self.unit, &[self.time]);
+        write!(
+            f,
+            "{}/{}",
+            PaceTime::over(self.time, split),
+            PaceSplit(split.unwrap_or(1.0), self.unit)
+        )
+    }
+}
+
+// Track splits first, so `72s/400m` prints as `1:12/400m` rather than `18s/100m`
+const METER_SPLITS: [f64; 9] = [1.0, 400.0, 200.0, 100.0, 800.0, 1000.0, 1600.0, 50.0, 10.0];
+const YARD_SPLITS: [f64; 4] = [1.0, 100.0, 440.0, 880.0];
+
+// Other whole splits up to this are tried next, rounder ones first, so `1:10/300m` prints
+// back as written rather than as `0:07/30m`
+const MAX_SPLIT: u32 = 10_000;
+
+/// Smallest common distance over which the paces come out as whole seconds, paces are
+/// stored per one unit so `1:30/400m` would otherwise print as a fraction of a second
+fn pace_split(unit: LengthUnit, times: &[f64]) -> Option<f64> {
+    let (splits, short): (&[f64], bool) = match unit {
+        LengthUnit::Meters => (&METER_SPLITS, true),
+        LengthUnit::Yards => (&YARD_SPLITS, true),
+        LengthUnit::Feet => (&[1.0], true),
+        LengthUnit::Miles | LengthUnit::Kilometers => (&[1.0], false),
+    };
+    let whole = |split: &f64| {
+        times
+            .iter()
+            .all(|time| (time * split).round() / split == *time)
+    };
+    splits.iter().copied().find(whole).or_else(|| match short {
+        true => [100, 10, 1].into_iter().find_map(|step| {
+            (1..=MAX_SPLIT / step)
+                .map(|split| f64::from(split * step))
+                .find(whole)
+        }),
+        false => None,
+    })
+}
+
+struct PaceSplit(f64, LengthUnit);
+
+impl fmt::Display for PaceSplit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 == 1.0 {
+            true => write!(f, "{}", self.1),
+            false => write!(f, "{}{}", self.0, self.1),
+        }
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.value, self.unit)
+    }
+}
+
+impl fmt::Display for SpeedUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unit = match self {
+            SpeedUnit::KilometersPerHour => "km/h",
+            SpeedUnit::MilesPerHour => "mph",
+        };
+        write!(f, "{}", unit)
+    }
+}
+
+impl fmt::Display for AlertKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            AlertKind::HeartRate => "heart rate",
+            AlertKind::Power => "power",
+            AlertKind::Cadence => "cadence",
+            AlertKind::Pace => "pace",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+impl fmt::Display for HeartRateZone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let zone = match self {
+            HeartRateZone::Z1 => "z1",
+            HeartRateZone::Z2 => "z2",
+            HeartRateZone::Z3 => "z3",
+            HeartRateZone::Z4 => "z4",
+            HeartRateZone::Z5 => "z5",
+        };
+        write!(f, "{}", zone)
+    }
+}
+
+impl fmt::Display for TrainingPace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pace = match self {
+            TrainingPace::Easy => "E",
+            TrainingPace::Marathon => "MP",
+            TrainingPace::HalfMarathon => "HMP",
+            TrainingPace::Threshold => "T",
+            TrainingPace::Interval => "I",
+            TrainingPace::Repetition => "R",
+        };
+        write!(f, "{}", pace)
+    }
+}
+
+impl fmt::Display for LengthUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unit = match self {
+            LengthUnit::Miles => "mi",
+            LengthUnit::Yards => "yd",
+            LengthUnit::Feet => "ft",
+            LengthUnit::Meters => "m",
+            LengthUnit::Kilometers => "km",
+        };
+        write!(f, "{}", unit)
+    }
+}
+
+impl fmt::Display for TimeUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unit = match self {
+            TimeUnit::Seconds => "sec",
+            TimeUnit::Minutes => "min",
+            TimeUnit::Hours => "hr",
+        };
+        write!(f, "{}", unit)
+    }
+}
+
+/// Seconds printed as `m:ss`, the only time format a pace accepts
+struct PaceTime(f64);
+
+impl PaceTime {
+    /// Time over `split` units, kept to the fraction of a second when no split fits
+    fn over(time: f64, split: Option<f64>) -> Self {
+        match split {
+            Some(split) => PaceTime((time * split).round()),
+            None => PaceTime(time),
+        }
+    }
+}
+
+impl fmt::Display for PaceTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let minutes = (self.0 / 60.0).floor();
+        let seconds = self.0 - minutes * 60.0;
+        // As few decimals as read back to the same time, `5:00.4` rather than `5:00`
+        let seconds = (0..=9)
+            .map(|decimals| format!("{:.*}", decimals, seconds))
+            .find(|printed| printed.parse::<f64>().map(|s| minutes * 60.0 + s) == Ok(self.0))
+            .unwrap_or_else(|| seconds.to_string());
+        let whole_digits = seconds.split('.').next().unwrap().len();
+        write!(
+            f,
+            "{}:{}{}",
+            minutes,
+            "0".repeat(2usize.saturating_sub(whole_digits)),
+            seconds
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests;
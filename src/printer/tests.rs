@@ -0,0 +1,107 @@
+use super::*;
+use crate::parser::parse_workout;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_to_dsl() {
+    let input = "1 mile warmup + 3 x (0.5 miles @z3 + 1 mile recovery) + 0.5 miles cooldown";
+    let workout = parse_workout(input).unwrap();
+    assert_eq!(
+        to_dsl(&workout),
+        "1 mi warmup + 3 x (0.5 mi @z3 + 1 mi recovery) + 0.5 mi cooldown"
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_to_dsl_alerts() {
+    let input = "30 minutes @7:05/mile + 400 meters @1:30-1:35/kilometer + 10 seconds @z5";
+    let workout = parse_workout(input).unwrap();
+    assert_eq!(
+        to_dsl(&workout),
+        "30 min @7:05/mi + 400 m @1:30-1:35/km + 10 sec @z5"
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_to_dsl_pace_split() {
+    let input = "400m @72s/400m + 200m @34-36s/200m + 1 km @3:30/km + 1 km @1:10/300m";
+    let workout = parse_workout(input).unwrap();
+    assert_eq!(
+        to_dsl(&workout),
+        "400 m @1:12/400m + 200 m @1:08-1:12/400m + 1 km @3:30/km + 1 km @1:10/300m"
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_to_dsl_pace_units() {
+    let pace = |time: f64, unit: LengthUnit| Pace { time, unit };
+    // Both ends of a range print in the unit of the first one
+    let alert = Alert::PaceRange {
+        min: pace(300.0, LengthUnit::Kilometers),
+        max: pace(
+            400.0 * length_unit_to_miles(LengthUnit::Miles)
+                / length_unit_to_miles(LengthUnit::Kilometers),
+            LengthUnit::Miles,
+        ),
+    };
+    assert_eq!(alert.to_string(), "5:00-6:40/km");
+
+    let alert = Alert::SpeedRange {
+        min: Speed {
+            value: 12.0,
+            unit: SpeedUnit::KilometersPerHour,
+        },
+        max: Speed {
+            value: 8.0,
+            unit: SpeedUnit::MilesPerHour,
+        },
+    };
+    assert_eq!(alert.to_string(), "12-12.874756km/h");
+
+    // Fractions of a second are kept when no split makes them whole
+    let alert = Alert::PaceThreshold(pace(301.2345, LengthUnit::Kilometers));
+    assert_eq!(alert.to_string(), "5:01.2345/km");
+
+    // Track splits are only for meters
+    let alert = Alert::PaceThreshold(pace(450.5, LengthUnit::Miles));
+    assert_eq!(alert.to_string(), "7:30.5/mi");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_to_dsl_keywords() {
+    // Synonyms print back in their canonical spelling
+    let workout = parse_workout("lap warmup + hm + 10k").unwrap();
+    assert_eq!(to_dsl(&workout), "open warmup + half marathon + 10k");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_to_dsl_round_trip() {
+    let inputs = [
+        "1 mile",
+        "15 minutes warmup + 2 x (1 km @4:00/km + 90 sec recovery) + 1 hr cooldown",
+        "3 x (4 x (200m @z5 + 200m recovery) + 3 min recovery) + 2 x (1 mile @z4)",
+        "100 yards + 1000 feet @6:00-6:30/mile + 0.25 miles @z2 recovery",
+        "10 km @12:00/km",
+        "20 min @150bpm + 3 x (1 km @165-175bpm + 2 min @<130bpm recovery)",
+        "1 km @4:00-4:10/km @z4 + 5 min @180spm @<150bpm @250W",
+        "5 min @180spm + 4 x (30 sec @175-185spm + 30 sec recovery)",
+        "5 min @260-280W + 3 x (3 min @105%CP + 2 min @180W recovery) + 1 mile @92.5%FTP",
+        "20 min @12km/h + 4 x (1 mile @7.5-8mph + 2 min @5mph recovery)",
+        "2 mi @E + 4 x (1 mile @T + 1 min @E recovery) + 10 mi @MP @<160bpm + 3 x (1 km @I + 200m @R)",
+        "30 min @z1-z2 + 4 x (1 km @z4-5 @180spm + 2 min @z1-1 recovery)",
+        "open warmup + 4 x (1 km @z4 + open @z1 recovery) + open cooldown",
+        "1 mile or 8 min @z4 + 3 x (2 km or 10 min @T + 1 min recovery)",
+        "mile warmup + 2 x (5k @z3 + 10k @4:00/km) + half marathon + marathon @<150bpm",
+        "1:15:00 warmup + 3 x (4:30 @z4 + 1m30s recovery) + 1h cooldown",
+        "8 x (400m @72s/400m + 200m @45s/200m recovery) + 1 mile @1:30-1:35/400m",
+        "10 min @15:01/2mi + 10 min @7:30-7:15.5/mi + 400 yards @1:20/100yd + 1 km @5:01.2345/km",
+    ];
+
+    for input in inputs {
+        let workout = parse_workout(input).unwrap();
+        let printed = to_dsl(&workout);
+        let reparsed = parse_workout(&printed).unwrap();
+        assert_eq!(reparsed, workout, "Round trip failed for input: {}", input);
+        assert_eq!(to_dsl(&reparsed), printed);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::error::ExpandError;
+use crate::evaluator::AlertEvaluator;
+use crate::mileage::{length_unit_to_miles, time_to_seconds};
+use crate::models::*;
+
+/// Runs a workout live: feed it samples and button presses, it answers with events
+pub struct Session {
+    steps: Vec<ExpandedStep>,
+    pace_map: PaceMap,
+    config: EvaluatorConfig,
+    state: SessionState,
+    current: usize,
+    elapsed: f64,  // Active seconds into the current step
+    distance: f64, // Meters into the current step
+    last: Option<Sample>,
+    evaluators: Vec<AlertEvaluator>, // One for each alert of the current step
+}
+
+impl Session {
+    pub fn new(
+        workout: &Workout,
+        pace_map: PaceMap,
+        config: EvaluatorConfig,
+    ) -> Result<Self, ExpandError> {
+        Ok(Session {
+            steps: workout.expand()?,
+            pace_map,
+            config,
+            state: SessionState::Ready,
+            current: 0,
+            elapsed: 0.0,
+            distance: 0.0,
+            last: None,
+            evaluators: Vec::new(),
+        })
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    pub fn current_step(&self) -> Option<&ExpandedStep> {
+        match self.state {
+            SessionState::Running | SessionState::Paused => self.steps.get(self.current),
+            _ => None,
+        }
+    }
+
+    pub fn start(&mut self) -> Vec<SessionEvent> {
+        let mut events = Vec::new();
+        if self.state == SessionState::Ready {
+            self.state = SessionState::Running;
+            // Time and distance only count from the first sample after the start
+            self.last = None;
+            self.begin_step(&mut events);
+        }
+        events
+    }
+
+    pub fn push_sample(&mut self, sample: Sample) -> Vec<SessionEvent> {
+        let mut events = Vec::new();
+        let last = self.last.replace(sample.clone());
+        if self.state != SessionState::Running {
+            return events;
+        }
+
+        // Whatever happened while paused is skipped over by the next sample
+        if let Some(last) = last {
+            self.elapsed += (sample.elapsed - last.elapsed).max(0.0);
+            self.distance += (sample.distance - last.distance).max(0.0);
+        }
+
+        self.check_alerts(&sample, &mut events);
+        if self.goal_reached() {
+            self.complete_step(&mut events);
+        }
+        events
+    }
+
+    /// Ends the current step, the only way out of an open one
+    pub fn lap(&mut self) -> Vec<SessionEvent> {
+        let mut events = Vec::new();
+        if self.current_step().is_some() {
+            self.complete_step(&mut events);
+        }
+        events
+    }
+
+    pub fn skip(&mut self) -> Vec<SessionEvent> {
+        let mut events = Vec::new();
+        if self.current_step().is_some() {
+            events.push(SessionEvent::StepSkipped {
+                index: self.current as u32,
+            });
+            self.next_step(&mut events);
+        }
+        events
+    }
+
+    pub fn pause(&mut self) -> Vec<SessionEvent> {
+        match self.state {
+            SessionState::Running => {
+                self.state = SessionState::Paused;
+                vec![SessionEvent::Paused]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn resume(&mut self) -> Vec<SessionEvent> {
+        match self.state {
+            SessionState::Paused => {
+                self.state = SessionState::Running;
+                vec![SessionEvent::Resumed]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn begin_step(&mut self, events: &mut Vec<SessionEvent>) {
+        self.elapsed = 0.0;
+        self.distance = 0.0;
+        match self.steps.get(self.current) {
+            Some(step) => {
+                self.evaluators = step
+                    .step
+                    .alerts
+                    .iter()
+                    .map(|alert| AlertEvaluator::new(alert, &self.pace_map, self.config.clone()))
+                    .collect();
+                events.push(SessionEvent::StepStarted {
+                    index: self.current as u32,
+                });
+            }
+            None => {
+                self.state = SessionState::Finished;
+                events.push(SessionEvent::Finished);
+            }
+        }
+    }
+
+    fn complete_step(&mut self, events: &mut Vec<SessionEvent>) {
+        events.push(SessionEvent::StepCompleted {
+            index: self.current as u32,
+            elapsed: self.elapsed,
+            distance: self.distance,
+        });
+        self.next_step(events);
+    }
+
+    fn next_step(&mut self, events: &mut Vec<SessionEvent>) {
+        self.current += 1;
+        self.begin_step(events);
+    }
+
+    fn goal_reached(&self) -> bool {
+        let meters = |value: f64, unit: LengthUnit| {
+            value * length_unit_to_miles(unit) / length_unit_to_miles(LengthUnit::Meters)
+        };
+        match self.steps[self.current].step.goal {
+            Goal::Distance { value, unit } => self.distance >= meters(value, unit),
+            Goal::Race { distance } => {
+                let (value, unit) = distance.length();
+                self.distance >= meters(value, unit)
+            }
+            Goal::Duration { value, unit } => self.elapsed >= time_to_seconds(value, unit),
+            Goal::FirstOf {
+                distance,
+                length_unit,
+                duration,
+                time_unit,
+            } => {
+                self.distance >= meters(distance, length_unit)
+                    || self.elapsed >= time_to_seconds(duration, time_unit)
+            }
+            Goal::Open => false,
+        }
+    }
+
+    fn check_alerts(&mut self, sample: &Sample, events: &mut Vec<SessionEvent>) {
+        let step = &self.steps[self.current];
+        for (alert, evaluator) in step.step.alerts.iter().zip(&mut self.evaluators) {
+            let previous = evaluator.status();
+            if let Some(status) = evaluator.push(sample) {
+                if previous != Some(status) {
+                    events.push(SessionEvent::Alert {
+                        index: step.index,
+                        alert: alert.clone(),
+                        status,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,177 @@
+use super::*;
+use crate::fixtures::{sample, PACE_MAP};
+use crate::parser::parse_workout;
+use wasm_bindgen_test::*;
+
+fn session(input: &str) -> Session {
+    // Readings are taken as is, to check the session rather than the evaluator
+    let config = EvaluatorConfig {
+        tolerance: 0.0,
+        smoothing: 1,
+        hysteresis: 0.0,
+    };
+    Session::new(&parse_workout(input).unwrap(), PACE_MAP.clone(), config).unwrap()
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_session_runs_steps_in_order() {
+    let mut session = session("1 min warmup + 400m");
+    assert_eq!(session.state(), SessionState::Ready);
+    assert!(session.push_sample(sample(0.0, 0.0)).is_empty());
+
+    assert_eq!(
+        session.start(),
+        vec![SessionEvent::StepStarted { index: 0 }]
+    );
+    assert!(session.push_sample(sample(0.0, 0.0)).is_empty());
+    assert!(session.push_sample(sample(30.0, 100.0)).is_empty());
+    assert_eq!(
+        session.push_sample(sample(60.0, 200.0)),
+        vec![
+            SessionEvent::StepCompleted {
+                index: 0,
+                elapsed: 60.0,
+                distance: 200.0
+            },
+            SessionEvent::StepStarted { index: 1 },
+        ]
+    );
+    assert_eq!(session.current_step().unwrap().step.to_string(), "400 m");
+
+    assert!(session.push_sample(sample(120.0, 500.0)).is_empty());
+    assert_eq!(
+        session.push_sample(sample(150.0, 600.0)),
+        vec![
+            SessionEvent::StepCompleted {
+                index: 1,
+                elapsed: 90.0,
+                distance: 400.0
+            },
+            SessionEvent::Finished,
+        ]
+    );
+    assert_eq!(session.state(), SessionState::Finished);
+    assert!(session.current_step().is_none());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_session_pause_and_resume() {
+    let mut session = session("2 min");
+    session.start();
+    session.push_sample(sample(0.0, 0.0));
+    session.push_sample(sample(60.0, 200.0));
+
+    assert_eq!(session.pause(), vec![SessionEvent::Paused]);
+    assert!(session.pause().is_empty());
+    // Time spent paused does not count
+    assert!(session.push_sample(sample(300.0, 250.0)).is_empty());
+    assert_eq!(session.resume(), vec![SessionEvent::Resumed]);
+
+    assert!(session.push_sample(sample(330.0, 300.0)).is_empty());
+    assert_eq!(
+        session.push_sample(sample(360.0, 400.0)),
+        vec![
+            SessionEvent::StepCompleted {
+                index: 0,
+                elapsed: 120.0,
+                distance: 350.0
+            },
+            SessionEvent::Finished,
+        ]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_session_lap_and_skip() {
+    let mut session = session("open warmup + 2 x (1 km + 1 min recovery)");
+    session.start();
+    session.push_sample(sample(0.0, 0.0));
+    // Open steps only end with a lap
+    assert!(session.push_sample(sample(3600.0, 10000.0)).is_empty());
+    assert_eq!(
+        session.lap(),
+        vec![
+            SessionEvent::StepCompleted {
+                index: 0,
+                elapsed: 3600.0,
+                distance: 10000.0
+            },
+            SessionEvent::StepStarted { index: 1 },
+        ]
+    );
+
+    assert_eq!(
+        session.skip(),
+        vec![
+            SessionEvent::StepSkipped { index: 1 },
+            SessionEvent::StepStarted { index: 2 },
+        ]
+    );
+    assert!(session.current_step().unwrap().recovery);
+
+    session.skip();
+    session.skip();
+    assert_eq!(
+        session.skip(),
+        vec![
+            SessionEvent::StepSkipped { index: 4 },
+            SessionEvent::Finished
+        ]
+    );
+    assert!(session.skip().is_empty());
+    assert!(session.lap().is_empty());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_session_first_of_goal() {
+    let mut session = session("1 km or 5 min");
+    session.start();
+    session.push_sample(sample(0.0, 0.0));
+    let events = session.push_sample(sample(300.0, 800.0));
+    assert_eq!(events.last(), Some(&SessionEvent::Finished));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_session_alerts() {
+    let mut session = session("10 min @z2 @170-180spm");
+    session.start();
+
+    let reading = |elapsed: f64, minutes_per_km: f64, cadence: u32| Sample {
+        cadence: Some(cadence),
+        pace: Some(Pace {
+            time: minutes_per_km * 60.0,
+            unit: LengthUnit::Kilometers,
+        }),
+        ..sample(elapsed, 0.0)
+    };
+    let zone = Alert::HeartRate(HeartRateZone::Z2);
+    let cadence = Alert::CadenceRange { min: 170, max: 180 };
+    let event = |alert: &Alert, status| SessionEvent::Alert {
+        index: 0,
+        alert: alert.clone(),
+        status,
+    };
+
+    assert_eq!(
+        session.push_sample(reading(0.0, 5.5, 175)),
+        vec![
+            event(&zone, AlertStatus::InRange),
+            event(&cadence, AlertStatus::InRange)
+        ]
+    );
+    // Only changes are reported
+    assert!(session.push_sample(reading(1.0, 5.2, 178)).is_empty());
+    assert_eq!(
+        session.push_sample(reading(2.0, 4.5, 178)),
+        vec![event(&zone, AlertStatus::TooFast)]
+    );
+    assert_eq!(
+        session.push_sample(reading(3.0, 7.0, 165)),
+        vec![
+            event(&zone, AlertStatus::TooSlow),
+            event(&cadence, AlertStatus::TooSlow)
+        ]
+    );
+    // Missing readings leave the status alone
+    assert!(session.push_sample(sample(4.0, 0.0)).is_empty());
+}
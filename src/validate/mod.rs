@@ -0,0 +1,389 @@
+use crate::error::{Diagnostic, ErrorCode, Severity, Span};
+use crate::expand::MAX_UNROLLED_STEPS;
+use crate::mileage::{length_unit_to_miles, speed_to_pace, time_to_seconds, TRAINING_PACES};
+use crate::models::*;
+
+// Steps beyond these are accepted, but most likely a typo
+const LONG_STEP_MILES: f64 = 50.0;
+const LONG_STEP_SECONDS: f64 = 8.0 * 3600.0;
+
+// Heart rates a human can actually reach
+const MIN_HEART_RATE: u32 = 30;
+const MAX_HEART_RATE: u32 = 250;
+
+// Blocks repeated more often are most likely a typo
+pub(crate) const MAX_REPEATS: u32 = 1000;
+
+/// Spans of the parsed source, steps and blocks in the order they appear in the text
+#[derive(Default)]
+pub(crate) struct SourceMap {
+    pub steps: Vec<StepSpans>,
+    pub blocks: Vec<Span>,
+}
+
+pub(crate) struct StepSpans {
+    pub goal: Span,
+    pub alerts: Vec<Span>,
+}
+
+pub fn validate(workout: &Workout) -> Vec<Diagnostic> {
+    validate_source(workout, &SourceMap::default())
+}
+
+pub(crate) fn validate_source(workout: &Workout, source: &SourceMap) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        source,
+        diagnostics: Vec::new(),
+        step_index: 0,
+        block_index: 0,
+    };
+
+    // The text form always has an interval, only structured workouts can miss one
+    if workout.intervals.is_empty() {
+        validator.report(
+            Severity::Error,
+            ErrorCode::EmptyWorkout,
+            "Workout has no intervals".to_string(),
+            None,
+        );
+    }
+
+    if let Some(warmup) = &workout.warmup {
+        validator.check_step(warmup);
+    }
+    for interval in &workout.intervals {
+        validator.check_block(interval);
+    }
+    if let Some(cooldown) = &workout.cooldown {
+        validator.check_step(cooldown);
+    }
+
+    if workout.unrolled_steps() > MAX_UNROLLED_STEPS {
+        validator.report(
+            Severity::Error,
+            ErrorCode::NumberOutOfRange,
+            format!(
+                "Workout unrolls into more than {} steps",
+                MAX_UNROLLED_STEPS
+            ),
+            None,
+        );
+    }
+
+    validator.diagnostics
+}
+
+struct Validator<'a> {
+    source: &'a SourceMap,
+    diagnostics: Vec<Diagnostic>,
+    step_index: usize,
+    block_index: usize,
+}
+
+impl Validator<'_> {
+    fn report(&mut self, severity: Severity, code: ErrorCode, message: String, span: Option<Span>) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            code,
+            message,
+            span,
+        });
+    }
+
+    fn check_block(&mut self, block: &IntervalBlock) {
+        let span = self.source.blocks.get(self.block_index).copied();
+        self.block_index += 1;
+
+        if block.repeats == Some(0) {
+            self.report(
+                Severity::Error,
+                ErrorCode::ZeroRepeats,
+                "Block is repeated 0 times".to_string(),
+                span,
+            );
+        } else if block.repeats.is_some_and(|repeats| repeats > MAX_REPEATS) {
+            self.report(
+                Severity::Error,
+                ErrorCode::NumberOutOfRange,
+                format!("Block is repeated more than {} times", MAX_REPEATS),
+                span,
+            );
+        }
+
+        // Recoveries are compared with the last work step before them
+        let mut work = None;
+        for rep in &block.steps {
+            match rep {
+                IntervalRep::Step(step) => {
+                    if step.has_recovery {
+                        if let Some(work_step) = work {
+                            self.check_recovery(work_step, &step.step);
+                        }
+                    } else {
+                        work = Some(&step.step);
+                    }
+                    self.check_step(&step.step);
+                }
+                IntervalRep::Block(inner) => self.check_block(inner),
+            }
+        }
+    }
+
+    fn current_spans(&self) -> Option<&StepSpans> {
+        self.source.steps.get(self.step_index)
+    }
+
+    fn alert_span(&self, index: usize) -> Option<Span> {
+        self.current_spans()
+            .and_then(|s| s.alerts.get(index))
+            .copied()
+    }
+
+    fn check_step(&mut self, step: &WorkoutStep) {
+        let goal_span = self.current_spans().map(|s| s.goal);
+
+        let (empty, too_long) = match step.goal {
+            Goal::Distance { value, unit } => (
+                value <= 0.0,
+                value * length_unit_to_miles(unit) >= LONG_STEP_MILES,
+            ),
+            Goal::Duration { value, unit } => (
+                value <= 0.0,
+                time_to_seconds(value, unit) >= LONG_STEP_SECONDS,
+            ),
+            // Only too long when neither limit cuts it short
+            Goal::FirstOf {
+                distance,
+                length_unit,
+                duration,
+                time_unit,
+            } => (
+                distance <= 0.0 || duration <= 0.0,
+                distance * length_unit_to_miles(length_unit) >= LONG_STEP_MILES
+                    && time_to_seconds(duration, time_unit) >= LONG_STEP_SECONDS,
+            ),
+            // Race distances are fixed and never longer than a marathon
+            Goal::Race { .. } | Goal::Open => (false, false),
+        };
+        if empty {
+            self.report(
+                Severity::Error,
+                ErrorCode::ZeroGoal,
+                format!("Step `{}` has an empty goal", step.goal),
+                goal_span,
+            );
+        } else if too_long {
+            self.report(
+                Severity::Warning,
+                ErrorCode::LongStep,
+                format!("Step `{}` is unusually long", step.goal),
+                goal_span,
+            );
+        }
+
+        for (index, alert) in step.alerts.iter().enumerate() {
+            let alert_span = self.alert_span(index);
+            if step.alerts[..index]
+                .iter()
+                .any(|other| other.kind() == alert.kind())
+            {
+                self.report(
+                    Severity::Error,
+                    ErrorCode::DuplicateAlert,
+                    format!("Step `{}` has more than one {} alert", step, alert.kind()),
+                    alert_span,
+                );
+            }
+            self.check_alert(alert, alert_span);
+        }
+
+        self.step_index += 1;
+    }
+
+    fn check_alert(&mut self, alert: &Alert, alert_span: Option<Span>) {
+        let invalid_pace = match alert {
+            Alert::PaceThreshold(pace) => pace.time <= 0.0,
+            Alert::PaceRange { min, max } => min.time <= 0.0 || max.time <= 0.0,
+            _ => false,
+        };
+        if invalid_pace {
+            self.report(
+                Severity::Error,
+                ErrorCode::InvalidPace,
+                format!("Pace target `{}` is not valid", alert),
+                alert_span,
+            );
+        }
+
+        let invalid_speed = match alert {
+            Alert::SpeedThreshold(speed) => speed.value <= 0.0,
+            Alert::SpeedRange { min, max } => min.value <= 0.0 || max.value <= 0.0,
+            _ => false,
+        };
+        if invalid_speed {
+            self.report(
+                Severity::Error,
+                ErrorCode::InvalidSpeed,
+                format!("Speed target `{}` is not valid", alert),
+                alert_span,
+            );
+        }
+
+        let heart_rates = match alert {
+            Alert::HeartRateThreshold { bpm, .. } => vec![*bpm],
+            Alert::HeartRateRange { min, max } => vec![*min, *max],
+            _ => vec![],
+        };
+        if let Some(bpm) = heart_rates
+            .iter()
+            .find(|bpm| !(MIN_HEART_RATE..=MAX_HEART_RATE).contains(bpm))
+        {
+            self.report(
+                Severity::Error,
+                ErrorCode::InvalidHeartRate,
+                format!("Heart rate {}bpm is out of range", bpm),
+                alert_span,
+            );
+        } else if let Alert::HeartRateRange { min, max } = alert {
+            if min > max {
+                self.report(
+                    Severity::Error,
+                    ErrorCode::InvalidHeartRate,
+                    format!("Heart rate range {}-{}bpm is reversed", min, max),
+                    alert_span,
+                );
+            }
+        } else if let Alert::HeartRateZoneRange { min, max } = alert {
+            if zone_index(min) > zone_index(max) {
+                self.report(
+                    Severity::Error,
+                    ErrorCode::InvalidHeartRate,
+                    format!("Zone range {} is reversed", alert),
+                    alert_span,
+                );
+            }
+        }
+
+        let invalid_power = match alert {
+            Alert::PowerThreshold { watts } => *watts == 0,
+            Alert::PowerRange { min, max } => *min == 0 || min > max,
+            Alert::CriticalPower { percent } => *percent <= 0.0,
+            _ => false,
+        };
+        if invalid_power {
+            self.report(
+                Severity::Error,
+                ErrorCode::InvalidPower,
+                format!("Power target `{}` is not valid", alert),
+                alert_span,
+            );
+        }
+
+        let invalid_cadence = match alert {
+            Alert::CadenceThreshold { spm } => *spm == 0,
+            Alert::CadenceRange { min, max } => *min == 0 || min > max,
+            _ => false,
+        };
+        if invalid_cadence {
+            self.report(
+                Severity::Error,
+                ErrorCode::InvalidCadence,
+                format!("Cadence target `{}` is not valid", alert),
+                alert_span,
+            );
+        }
+    }
+
+    /// Must run before the recovery step itself is checked, to read its spans
+    fn check_recovery(&mut self, work: &WorkoutStep, recovery: &WorkoutStep) {
+        for (index, recovery_alert) in recovery.alerts.iter().enumerate() {
+            let Some((recovery_kind, recovery_effort)) = alert_effort(recovery_alert) else {
+                continue;
+            };
+            let harder =
+                work.alerts
+                    .iter()
+                    .filter_map(alert_effort)
+                    .any(|(work_kind, work_effort)| {
+                        work_kind == recovery_kind && recovery_effort > work_effort
+                    });
+
+            if harder {
+                let span = self.alert_span(index);
+                self.report(
+                    Severity::Warning,
+                    ErrorCode::RecoveryFasterThanWork,
+                    format!(
+                        "Recovery `{}` is harder than the work step `{}`",
+                        recovery, work
+                    ),
+                    span,
+                );
+            }
+        }
+    }
+}
+
+/// Number of steps a block expands into, saturating instead of overflowing
+fn seconds_per_mile(pace: &Pace) -> f64 {
+    pace.time / length_unit_to_miles(pace.unit)
+}
+
+#[derive(PartialEq)]
+enum EffortKind {
+    Zone,
+    HeartRate,
+    Power,
+    CriticalPower,
+    Pace,
+    TrainingPace,
+}
+
+/// How hard an alert is, only comparable between alerts of the same kind
+fn alert_effort(alert: &Alert) -> Option<(EffortKind, f64)> {
+    let average = |min: u32, max: u32| (min as f64 + max as f64) / 2.0;
+    match alert {
+        Alert::HeartRate(zone) => Some((EffortKind::Zone, zone_index(zone) as f64)),
+        Alert::HeartRateZoneRange { min, max } => Some((
+            EffortKind::Zone,
+            average(zone_index(min) as u32, zone_index(max) as u32),
+        )),
+        Alert::TrainingPace(pace) => Some((
+            EffortKind::TrainingPace,
+            TRAINING_PACES.iter().position(|p| p == pace).unwrap() as f64,
+        )),
+        Alert::HeartRateThreshold { bpm, .. } => Some((EffortKind::HeartRate, *bpm as f64)),
+        Alert::HeartRateRange { min, max } => Some((EffortKind::HeartRate, average(*min, *max))),
+        Alert::PowerThreshold { watts } => Some((EffortKind::Power, *watts as f64)),
+        Alert::PowerRange { min, max } => Some((EffortKind::Power, average(*min, *max))),
+        Alert::CriticalPower { percent } => Some((EffortKind::CriticalPower, *percent)),
+        // Faster paces are harder
+        Alert::PaceThreshold(pace) => Some((EffortKind::Pace, -seconds_per_mile(pace))),
+        // A higher cadence is not necessarily harder
+        Alert::CadenceThreshold { .. } | Alert::CadenceRange { .. } => None,
+        Alert::SpeedThreshold(speed) => {
+            Some((EffortKind::Pace, -seconds_per_mile(&speed_to_pace(speed))))
+        }
+        Alert::SpeedRange { min, max } => Some((
+            EffortKind::Pace,
+            -(seconds_per_mile(&speed_to_pace(min)) + seconds_per_mile(&speed_to_pace(max))) / 2.0,
+        )),
+        Alert::PaceRange { min, max } => Some((
+            EffortKind::Pace,
+            -(seconds_per_mile(min) + seconds_per_mile(max)) / 2.0,
+        )),
+    }
+}
+
+fn zone_index(zone: &HeartRateZone) -> u8 {
+    match zone {
+        HeartRateZone::Z1 => 1,
+        HeartRateZone::Z2 => 2,
+        HeartRateZone::Z3 => 3,
+        HeartRateZone::Z4 => 4,
+        HeartRateZone::Z5 => 5,
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,300 @@
+use super::*;
+use crate::parser::{lint_workout, parse_workout};
+use wasm_bindgen_test::*;
+
+fn codes(diagnostics: &[Diagnostic]) -> Vec<(Severity, ErrorCode)> {
+    diagnostics.iter().map(|d| (d.severity, d.code)).collect()
+}
+
+fn spanned<'a>(input: &'a str, diagnostic: &Diagnostic) -> &'a str {
+    let span = diagnostic.span.unwrap();
+    &input[span.start as usize..span.end as usize]
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_valid_workout() {
+    let input = "1 mile warmup + 3 x (800m @3:00-2:50/km + 2 min @z1 recovery) + 1 mile cooldown";
+    assert_eq!(lint_workout(input).unwrap(), vec![]);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_empty_workout() {
+    let mut workout = parse_workout("10 min warmup + 1 km + 5 min cooldown").unwrap();
+    workout.intervals.clear();
+    assert_eq!(
+        codes(&validate(&workout)),
+        vec![(Severity::Error, ErrorCode::EmptyWorkout)]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_zero_repeats() {
+    let input = "1 mile + 0 x (400m @z5)";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![(Severity::Error, ErrorCode::ZeroRepeats)]
+    );
+    assert_eq!(spanned(input, &diagnostics[0]), "0 x (400m @z5)");
+
+    let error = parse_workout(input).unwrap_err();
+    assert_eq!(error.code, ErrorCode::ZeroRepeats);
+    assert_eq!(error.column, 10);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_too_many_repeats() {
+    let input = "1 mile + 4000000000 x (1 km)";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![
+            (Severity::Error, ErrorCode::NumberOutOfRange),
+            (Severity::Error, ErrorCode::NumberOutOfRange)
+        ]
+    );
+    assert_eq!(spanned(input, &diagnostics[0]), "4000000000 x (1 km)");
+    assert_eq!(diagnostics[1].span, None);
+
+    // Nested blocks are each below the limit, but not their product
+    let input = "1 mile + 200 x (200 x (1 km))";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![(Severity::Error, ErrorCode::NumberOutOfRange)]
+    );
+
+    assert!(parse_workout("1 mile + 1000 x (1 km + 1 min)").is_ok());
+    let error = parse_workout("1 mile + 1001 x (1 km)").unwrap_err();
+    assert_eq!(error.code, ErrorCode::NumberOutOfRange);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_zero_goal() {
+    let input = "1 mile warmup + 2 x (0 miles @z4 + 1 min recovery)";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![(Severity::Error, ErrorCode::ZeroGoal)]
+    );
+    assert_eq!(spanned(input, &diagnostics[0]), "0 miles");
+    assert!(parse_workout(input).is_err());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_long_step() {
+    let input = "50 miles + 10 hours + 49 miles";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![
+            (Severity::Warning, ErrorCode::LongStep),
+            (Severity::Warning, ErrorCode::LongStep)
+        ]
+    );
+    assert_eq!(spanned(input, &diagnostics[1]), "10 hours");
+
+    // Warnings do not prevent parsing
+    assert!(parse_workout(input).is_ok());
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_pace_range_order() {
+    // Ranges are written fast-to-slow or slow-to-fast
+    for input in ["1 mile @7:30-8:00/mile", "1 mile @8:00-7:30/mile"] {
+        assert_eq!(lint_workout(input).unwrap(), vec![]);
+    }
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_recovery_faster_than_work() {
+    let input =
+        "4 x (1 km @4:00/km + 400m @3:50/km recovery) + 3 x (1 min @z3 + 1 min @z4 recovery)";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![
+            (Severity::Warning, ErrorCode::RecoveryFasterThanWork),
+            (Severity::Warning, ErrorCode::RecoveryFasterThanWork)
+        ]
+    );
+    assert_eq!(spanned(input, &diagnostics[0]), "3:50/km");
+    assert_eq!(spanned(input, &diagnostics[1]), "z4");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_nested_spans() {
+    let input = "3 x (2 x (0 m + 200m recovery) + 0 x (1 min))";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![
+            (Severity::Error, ErrorCode::ZeroGoal),
+            (Severity::Error, ErrorCode::ZeroRepeats)
+        ]
+    );
+    assert_eq!(spanned(input, &diagnostics[0]), "0 m");
+    assert_eq!(spanned(input, &diagnostics[1]), "0 x (1 min)");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_validate_without_source() {
+    let workout = Workout {
+        warmup: None,
+        intervals: vec![IntervalBlock {
+            repeats: Some(0),
+            steps: vec![IntervalRep::Step(IntervalStep {
+                step: WorkoutStep {
+                    goal: Goal::Duration {
+                        value: 0.0,
+                        unit: TimeUnit::Minutes,
+                    },
+                    alerts: vec![],
+                },
+                has_recovery: false,
+            })],
+        }],
+        cooldown: None,
+    };
+    let diagnostics = validate(&workout);
+    assert_eq!(
+        codes(&diagnostics),
+        vec![
+            (Severity::Error, ErrorCode::ZeroRepeats),
+            (Severity::Error, ErrorCode::ZeroGoal)
+        ]
+    );
+    assert!(diagnostics.iter().all(|d| d.span.is_none()));
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_invalid_heart_rate() {
+    let input = "10 min @300bpm + 10 min @160-150bpm + 10 min @<140bpm";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![
+            (Severity::Error, ErrorCode::InvalidHeartRate),
+            (Severity::Error, ErrorCode::InvalidHeartRate)
+        ]
+    );
+    assert_eq!(spanned(input, &diagnostics[0]), "300bpm");
+    assert_eq!(spanned(input, &diagnostics[1]), "160-150bpm");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_reversed_zone_range() {
+    let input = "10 min @z3-z1 + 10 min @z1-z3";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![(Severity::Error, ErrorCode::InvalidHeartRate)]
+    );
+    assert_eq!(spanned(input, &diagnostics[0]), "z3-z1");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_recovery_heart_rate_above_work() {
+    let input = "4 x (3 min @150-160bpm + 2 min @<170bpm recovery)";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![(Severity::Warning, ErrorCode::RecoveryFasterThanWork)]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_invalid_power() {
+    let input = "10 min @0W + 10 min @300-250W + 10 min @0%CP + 10 min @250-300W";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![
+            (Severity::Error, ErrorCode::InvalidPower),
+            (Severity::Error, ErrorCode::InvalidPower),
+            (Severity::Error, ErrorCode::InvalidPower)
+        ]
+    );
+    assert_eq!(spanned(input, &diagnostics[1]), "300-250W");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_recovery_power_above_work() {
+    let input =
+        "4 x (3 min @95%CP + 2 min @100%CP recovery) + 2 x (1 min @300W + 1 min @80%CP recovery)";
+    let diagnostics = lint_workout(input).unwrap();
+    // Watts and percentages of critical power are not compared
+    assert_eq!(
+        codes(&diagnostics),
+        vec![(Severity::Warning, ErrorCode::RecoveryFasterThanWork)]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_invalid_cadence() {
+    let input = "10 min @0spm + 10 min @185-175spm + 10 min @175-185spm";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![
+            (Severity::Error, ErrorCode::InvalidCadence),
+            (Severity::Error, ErrorCode::InvalidCadence)
+        ]
+    );
+    assert_eq!(spanned(input, &diagnostics[0]), "0spm");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_invalid_speed() {
+    let input = "10 min @0km/h + 10 min @8-7.5mph + 10 min @7.5-8mph + 1 km @5-0km/h";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![
+            (Severity::Error, ErrorCode::InvalidSpeed),
+            (Severity::Error, ErrorCode::InvalidSpeed)
+        ]
+    );
+    assert_eq!(spanned(input, &diagnostics[0]), "0km/h");
+    assert_eq!(spanned(input, &diagnostics[1]), "5-0km/h");
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_invalid_pace() {
+    let input = "10 min @0:00/km + 10 min @4:00-0:00/km + 10 min @0s/400m";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![
+            (Severity::Error, ErrorCode::InvalidPace),
+            (Severity::Error, ErrorCode::InvalidPace),
+            (Severity::Error, ErrorCode::InvalidPace)
+        ]
+    );
+    assert_eq!(spanned(input, &diagnostics[0]), "0:00/km");
+    assert_eq!(spanned(input, &diagnostics[1]), "4:00-0:00/km");
+
+    let error = parse_workout("10 min @0:00/km").unwrap_err();
+    assert_eq!(error.code, ErrorCode::InvalidPace);
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_recovery_speed_above_pace() {
+    let input = "3 x (1 km @4:00/km + 2 min @16km/h recovery)";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![(Severity::Warning, ErrorCode::RecoveryFasterThanWork)]
+    );
+}
+
+#[wasm_bindgen_test(unsupported = test)]
+fn test_recovery_training_pace_above_work() {
+    let input = "3 x (1 km @T + 2 min @R recovery) + 3 x (1 mile @I + 2 min @E recovery)";
+    let diagnostics = lint_workout(input).unwrap();
+    assert_eq!(
+        codes(&diagnostics),
+        vec![(Severity::Warning, ErrorCode::RecoveryFasterThanWork)]
+    );
+    assert_eq!(spanned(input, &diagnostics[0]), "R");
+}
@@ -1,16 +1,199 @@
+use crate::error::{DashError, EstimateError, ExpandError, FitError, PaceMapError};
 use crate::models;
 
 use wasm_bindgen::prelude::*;
 
-#[wasm_bindgen(js_name = getWorkout)]
-pub fn get_workout(input: &str) -> Result<models::Workout, JsError> {
-    match crate::parser::parse_workout(input) {
-        Ok(workout) => Ok(workout),
-        Err(e) => Err(JsError::new(&format!("{}", e))),
+impl From<DashError> for JsValue {
+    fn from(error: DashError) -> Self {
+        serde_wasm_bindgen::to_value(&error).unwrap_throw()
     }
 }
 
+impl From<ExpandError> for JsValue {
+    fn from(error: ExpandError) -> Self {
+        serde_wasm_bindgen::to_value(&error).unwrap_throw()
+    }
+}
+
+impl From<EstimateError> for JsValue {
+    fn from(error: EstimateError) -> Self {
+        serde_wasm_bindgen::to_value(&error).unwrap_throw()
+    }
+}
+
+impl From<PaceMapError> for JsValue {
+    fn from(error: PaceMapError) -> Self {
+        serde_wasm_bindgen::to_value(&error).unwrap_throw()
+    }
+}
+
+impl From<FitError> for JsValue {
+    fn from(error: FitError) -> Self {
+        serde_wasm_bindgen::to_value(&error).unwrap_throw()
+    }
+}
+
+#[wasm_bindgen(js_name = getWorkout)]
+pub fn get_workout(input: &str) -> Result<models::Workout, DashError> {
+    crate::parser::parse_workout(input)
+}
+
+#[wasm_bindgen(js_name = lintWorkout, unchecked_return_type = "Diagnostic[]")]
+pub fn lint_workout(input: &str) -> Result<JsValue, DashError> {
+    let diagnostics = crate::parser::lint_workout(input)?;
+    Ok(serde_wasm_bindgen::to_value(&diagnostics).unwrap_throw())
+}
+
+#[wasm_bindgen(unchecked_return_type = "Diagnostic[]")]
+pub fn validate(workout: models::Workout) -> JsValue {
+    serde_wasm_bindgen::to_value(&crate::validate::validate(&workout)).unwrap_throw()
+}
+
+#[wasm_bindgen(js_name = expandWorkout, unchecked_return_type = "ExpandedStep[]")]
+pub fn expand_workout(workout: models::Workout) -> Result<JsValue, ExpandError> {
+    Ok(serde_wasm_bindgen::to_value(&workout.expand()?).unwrap_throw())
+}
+
 #[wasm_bindgen(js_name = getMileage)]
-pub fn get_mileage(pace_map: models::PaceMap, workout: models::Workout) -> models::Mileage {
+pub fn get_mileage(
+    pace_map: models::PaceMap,
+    workout: models::Workout,
+) -> Result<models::Mileage, EstimateError> {
     crate::mileage::get_mileage(&pace_map, &workout)
 }
+
+#[wasm_bindgen(js_name = getDuration)]
+pub fn get_duration(
+    pace_map: models::PaceMap,
+    workout: models::Workout,
+) -> Result<models::WorkoutDuration, EstimateError> {
+    crate::duration::get_duration(&pace_map, &workout)
+}
+
+#[wasm_bindgen(js_name = getBreakdown)]
+pub fn get_breakdown(
+    pace_map: models::PaceMap,
+    workout: models::Workout,
+) -> Result<models::Breakdown, EstimateError> {
+    crate::breakdown::get_breakdown(&pace_map, &workout)
+}
+
+#[wasm_bindgen(js_name = getPaceMap)]
+pub fn get_pace_map(race: models::Goal, time: f64) -> Result<models::PaceMap, PaceMapError> {
+    crate::pace_map::get_pace_map(&race, time)
+}
+
+#[wasm_bindgen(js_name = toFit)]
+pub fn to_fit(
+    pace_map: models::PaceMap,
+    workout: models::Workout,
+    name: &str,
+) -> Result<Vec<u8>, FitError> {
+    crate::fit::to_fit(&pace_map, &workout, name)
+}
+
+#[wasm_bindgen(js_name = fromFit)]
+pub fn from_fit(bytes: &[u8]) -> Result<models::FitWorkout, FitError> {
+    crate::fit::from_fit(bytes)
+}
+
+#[wasm_bindgen(js_name = toDsl)]
+pub fn to_dsl(workout: models::Workout) -> String {
+    crate::printer::to_dsl(&workout)
+}
+
+#[wasm_bindgen]
+pub struct WorkoutSession {
+    session: crate::session::Session,
+}
+
+fn to_events(events: Vec<models::SessionEvent>) -> JsValue {
+    serde_wasm_bindgen::to_value(&events).unwrap_throw()
+}
+
+#[wasm_bindgen]
+impl WorkoutSession {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        workout: models::Workout,
+        pace_map: models::PaceMap,
+        config: Option<models::EvaluatorConfig>,
+    ) -> Result<WorkoutSession, ExpandError> {
+        Ok(WorkoutSession {
+            session: crate::session::Session::new(&workout, pace_map, config.unwrap_or_default())?,
+        })
+    }
+
+    #[wasm_bindgen(getter, unchecked_return_type = "SessionState")]
+    pub fn state(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.session.state()).unwrap_throw()
+    }
+
+    #[wasm_bindgen(getter, js_name = currentStep, unchecked_return_type = "ExpandedStep | undefined")]
+    pub fn current_step(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.session.current_step()).unwrap_throw()
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "SessionEvent[]")]
+    pub fn start(&mut self) -> JsValue {
+        to_events(self.session.start())
+    }
+
+    #[wasm_bindgen(js_name = pushSample, unchecked_return_type = "SessionEvent[]")]
+    pub fn push_sample(&mut self, sample: models::Sample) -> JsValue {
+        to_events(self.session.push_sample(sample))
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "SessionEvent[]")]
+    pub fn lap(&mut self) -> JsValue {
+        to_events(self.session.lap())
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "SessionEvent[]")]
+    pub fn skip(&mut self) -> JsValue {
+        to_events(self.session.skip())
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "SessionEvent[]")]
+    pub fn pause(&mut self) -> JsValue {
+        to_events(self.session.pause())
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "SessionEvent[]")]
+    pub fn resume(&mut self) -> JsValue {
+        to_events(self.session.resume())
+    }
+}
+
+#[wasm_bindgen]
+pub struct AlertEvaluator {
+    evaluator: crate::evaluator::AlertEvaluator,
+}
+
+#[wasm_bindgen]
+impl AlertEvaluator {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        alert: models::Alert,
+        pace_map: models::PaceMap,
+        config: Option<models::EvaluatorConfig>,
+    ) -> AlertEvaluator {
+        AlertEvaluator {
+            evaluator: crate::evaluator::AlertEvaluator::new(
+                &alert,
+                &pace_map,
+                config.unwrap_or_default(),
+            ),
+        }
+    }
+
+    #[wasm_bindgen(getter, unchecked_return_type = "AlertStatus | undefined")]
+    pub fn status(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.evaluator.status()).unwrap_throw()
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "AlertStatus | undefined")]
+    pub fn push(&mut self, sample: models::Sample) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.evaluator.push(&sample)).unwrap_throw()
+    }
+}